@@ -0,0 +1,390 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::process::ExitCode;
+
+use anyhow::{Context, Result, bail};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use mix_system::emulator::machine::{Fault, Machine};
+use mix_system::mixal::assemble::{Assembler, Definition, Dialect, Options};
+use mix_system::mixal::debug_info::DebugInfo;
+use mix_system::mixal::diagnostic::{self, Stage};
+use mix_system::mixal::disassemble::{disassemble, disassemble_word};
+use mix_system::mixal::image::Image;
+
+/// Exit status for errors not covered by a more specific code, e.g. unreadable files
+const EXIT_FAILURE: u8 = 1;
+/// Exit status when a line of MIXAL can't be parsed
+const EXIT_PARSE_ERROR: u8 = 3;
+/// Exit status when a program parses but can't be assembled, e.g. an undefined symbol
+const EXIT_ASSEMBLY_ERROR: u8 = 4;
+/// Exit status when the emulator stops on a fault, e.g. an invalid instruction
+const EXIT_FAULT: u8 = 5;
+
+const EXIT_CODES_HELP: &str = "\
+Exit status:
+  0  success
+  1  other errors, e.g. unreadable files
+  2  invalid command line arguments
+  3  MIXAL parse error
+  4  MIXAL assembly error
+  5  the program stopped on a fault";
+
+/// Returned when assembly reported errors, which have already been printed
+#[derive(Debug)]
+struct AssemblyFailed {
+    /// The stage of the first error
+    stage: Stage,
+    errors: usize,
+    /// Whether to print a summary, which would get in the way of machine-readable diagnostics
+    summarize: bool,
+}
+
+impl fmt::Display for AssemblyFailed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let plural = if self.errors == 1 { "" } else { "s" };
+        write!(
+            f,
+            "aborting due to {} previous error{}",
+            self.errors, plural
+        )
+    }
+}
+
+impl std::error::Error for AssemblyFailed {}
+
+#[derive(Parser)]
+#[command(name = "mix")]
+#[command(about = "Tools for Knuth's MIX computer")]
+#[command(after_help = EXIT_CODES_HELP)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Assemble a MIXAL program into a memory image
+    Asm(AsmArgs),
+    /// Run a memory image until HLT and print the registers
+    Run(ImageArgs),
+    /// Disassemble a memory image back into MIXAL
+    Dis(ImageArgs),
+    /// Step through a memory image interactively
+    Debug(ImageArgs),
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ImageFormat {
+    Text,
+    Binary,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DiagnosticsFormat {
+    /// The source line at fault, with carets under the problem
+    Human,
+    /// A JSON array of diagnostics
+    Json,
+    /// A SARIF 2.1.0 log, for code scanning tools
+    Sarif,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Color {
+    /// Color if stderr is a terminal and NO_COLOR isn't set
+    Auto,
+    Always,
+    Never,
+}
+
+impl Color {
+    fn enabled(self) -> bool {
+        match self {
+            Color::Auto => io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none(),
+            Color::Always => true,
+            Color::Never => false,
+        }
+    }
+}
+
+#[derive(Args)]
+struct AsmArgs {
+    /// MIXAL source file, read from stdin if not given
+    input: Option<String>,
+
+    /// Write the memory image to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Write an assembly listing to this file, or to stdout if "-"
+    #[arg(long)]
+    listing: Option<String>,
+
+    /// Format of the memory image
+    #[arg(long, value_enum, default_value_t = ImageFormat::Text)]
+    format: ImageFormat,
+
+    /// Write debug information mapping addresses back to the source to this file
+    #[arg(short = 'g', long)]
+    debug_info: Option<String>,
+
+    /// Treat warnings as errors
+    #[arg(long)]
+    strict: bool,
+
+    /// Flavor of MIXAL to accept: knuth, or mdk for GNU MDK's lowercase mnemonics,
+    /// quoted ALF strings, long symbols and '#' comments
+    #[arg(long, default_value = "knuth")]
+    dialect: Dialect,
+
+    /// Allow macros to be defined with MACRO and ENDM
+    #[arg(long)]
+    macros: bool,
+
+    /// Define a symbol before the first line, as if by NAME EQU value
+    #[arg(short = 'D', value_name = "NAME=value")]
+    define: Vec<Definition>,
+
+    /// Format of the errors and warnings written to stderr
+    #[arg(long, value_enum, default_value_t = DiagnosticsFormat::Human)]
+    diagnostics: DiagnosticsFormat,
+
+    /// When to color human-readable diagnostics
+    #[arg(long, value_enum, default_value_t = Color::Auto)]
+    color: Color,
+}
+
+#[derive(Args)]
+struct ImageArgs {
+    /// Memory image, in text or binary format, read from stdin if not given
+    image: Option<String>,
+
+    /// Debug information produced when the image was assembled
+    #[arg(short = 'g', long)]
+    debug_info: Option<String>,
+}
+
+/// Reads a file, or stdin if no path is given
+fn read_input(path: Option<&str>) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    match path {
+        Some(path) => data = fs::read(path).with_context(|| format!("Failed to read {}", path))?,
+        None => {
+            io::stdin()
+                .read_to_end(&mut data)
+                .context("Failed to read stdin")?;
+        }
+    }
+    Ok(data)
+}
+
+/// Writes to a file, or stdout if no path is given or the path is "-"
+fn write_output(path: Option<&str>, data: &[u8]) -> Result<()> {
+    match path {
+        Some(path) if path != "-" => {
+            fs::write(path, data).with_context(|| format!("Failed to write {}", path))
+        }
+        _ => io::stdout()
+            .write_all(data)
+            .context("Failed to write stdout"),
+    }
+}
+
+fn asm(args: AsmArgs) -> Result<()> {
+    let source = String::from_utf8(read_input(args.input.as_deref())?)
+        .context("MIXAL source is not valid UTF-8")?;
+    let file = args.input.as_deref().unwrap_or("<stdin>");
+    let options = Options {
+        strict: args.strict,
+        dialect: args.dialect,
+        macros: args.macros,
+        predefined: args.define,
+    };
+    let program = Assembler::new(options).assemble_str(&source, file);
+
+    match args.diagnostics {
+        DiagnosticsFormat::Human => {
+            let color = args.color.enabled();
+            for diagnostic in &program.diagnostics {
+                eprintln!("{}", diagnostic.render(&program.sources, color));
+            }
+        }
+        DiagnosticsFormat::Json => eprintln!("{}", diagnostic::to_json(&program.diagnostics)),
+        DiagnosticsFormat::Sarif => eprintln!("{}", diagnostic::to_sarif(&program.diagnostics)),
+    }
+    if let Some(first) = program.errors().next() {
+        return Err(AssemblyFailed {
+            stage: first.stage,
+            errors: program.errors().count(),
+            summarize: matches!(args.diagnostics, DiagnosticsFormat::Human),
+        }
+        .into());
+    }
+
+    let image = match args.format {
+        ImageFormat::Text => program.image.to_string().into_bytes(),
+        ImageFormat::Binary => program.image.to_binary(),
+    };
+    write_output(args.output.as_deref(), &image)?;
+
+    if let Some(path) = args.listing {
+        write_output(Some(&path), program.listing.to_string().as_bytes())?;
+    }
+    if let Some(path) = args.debug_info {
+        write_output(Some(&path), program.debug_info.to_string().as_bytes())?;
+    }
+    Ok(())
+}
+
+fn load(args: &ImageArgs) -> Result<(Image, Option<DebugInfo>)> {
+    let image = Image::load(&read_input(args.image.as_deref())?)?;
+    let debug_info = args
+        .debug_info
+        .as_deref()
+        .map(|path| -> Result<DebugInfo> { String::from_utf8(read_input(Some(path))?)?.parse() })
+        .transpose()?;
+    Ok((image, debug_info))
+}
+
+fn dis(args: ImageArgs) -> Result<()> {
+    let (image, debug_info) = load(&args)?;
+    write_output(None, disassemble(&image, debug_info.as_ref()).as_bytes())
+}
+
+/// Loads an image into a fresh machine
+fn boot(image: &Image) -> Result<Machine> {
+    let mut machine = Machine::default();
+    machine.load(image)?;
+    Ok(machine)
+}
+
+/// Adds the source of the faulting instruction to a fault, if there is debug info
+fn locate(fault: Fault, debug_info: Option<&DebugInfo>) -> anyhow::Error {
+    match debug_info {
+        Some(info) => {
+            let context = format!("at {}", info.describe(fault.location));
+            anyhow::Error::from(fault).context(context)
+        }
+        None => fault.into(),
+    }
+}
+
+fn run_image(args: ImageArgs) -> Result<()> {
+    let (image, debug_info) = load(&args)?;
+    let mut machine = boot(&image)?;
+    machine
+        .run()
+        .map_err(|fault| locate(fault, debug_info.as_ref()))?;
+    write_output(None, machine.to_string().as_bytes())
+}
+
+/// Shows the instruction at the machine's `pc`, e.g. "3000 STJ  3009"
+fn show_next(machine: &Machine, debug_info: Option<&DebugInfo>) -> String {
+    let (op, address) = disassemble_word(&machine.memory[machine.pc], debug_info);
+    let location = match debug_info {
+        Some(info) => info.describe(machine.pc),
+        None => format!("{:04}", machine.pc),
+    };
+    format!("{:<5}{}  ; {}", op, address, location)
+}
+
+const DEBUG_HELP: &str = "\
+s, <enter>  execute the next instruction
+c           continue until HLT
+r           show the registers
+m ADDRESS   show a word of memory
+q           quit";
+
+fn debug(args: ImageArgs) -> Result<()> {
+    if args.image.is_none() {
+        bail!("mix debug reads commands from stdin, so the image must be given as a file");
+    }
+    let (image, debug_info) = load(&args)?;
+    let debug_info = debug_info.as_ref();
+    let mut machine = boot(&image)?;
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut reported_halt = false;
+
+    // After HLT the final state can still be looked at, until q or the end of input
+    loop {
+        if machine.halted {
+            if !reported_halt {
+                println!("Halted after {} instructions", machine.steps);
+                reported_halt = true;
+            }
+        } else if machine.pc < machine.memory.len() {
+            println!("{}", show_next(&machine, debug_info));
+        }
+        print!("(mix) ");
+        io::stdout().flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            return Ok(());
+        };
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (None | Some("s" | "c"), _) if machine.halted => {
+                eprintln!("The program has halted")
+            }
+            (None | Some("s"), _) => {
+                if let Err(fault) = machine.step() {
+                    eprintln!("{:#}", locate(fault, debug_info));
+                }
+            }
+            (Some("c"), _) => {
+                if let Err(fault) = machine.run() {
+                    eprintln!("{:#}", locate(fault, debug_info));
+                }
+            }
+            (Some("r"), _) => print!("{}", machine),
+            (Some("m"), Some(address)) => match address.parse::<usize>() {
+                Ok(address) if address < machine.memory.len() => {
+                    println!("{:04} {}", address, machine.memory[address])
+                }
+                _ => eprintln!("Invalid address '{}'", address),
+            },
+            (Some("q"), _) => return Ok(()),
+            _ => println!("{}", DEBUG_HELP),
+        }
+    }
+}
+
+fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Asm(args) => asm(args),
+        Command::Run(args) => run_image(args),
+        Command::Dis(args) => dis(args),
+        Command::Debug(args) => debug(args),
+    }
+}
+
+/// Picks the exit status for an error, based on where it came from
+fn exit_code(err: &anyhow::Error) -> u8 {
+    if err.downcast_ref::<Fault>().is_some() {
+        return EXIT_FAULT;
+    }
+    match err.downcast_ref::<AssemblyFailed>() {
+        Some(failed) if failed.stage == Stage::Parse => EXIT_PARSE_ERROR,
+        Some(_) => EXIT_ASSEMBLY_ERROR,
+        None => EXIT_FAILURE,
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            let summarize = err
+                .downcast_ref::<AssemblyFailed>()
+                .is_none_or(|failed| failed.summarize);
+            if summarize {
+                eprintln!("error: {:#}", err);
+            }
+            ExitCode::from(exit_code(&err))
+        }
+    }
+}
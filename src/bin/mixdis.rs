@@ -0,0 +1,36 @@
+use std::fs;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+
+use mix_system::mixal::debug_info::DebugInfo;
+use mix_system::mixal::disassemble::disassemble;
+use mix_system::mixal::image::Image;
+
+#[derive(Parser)]
+#[command(name = "mixdis")]
+#[command(about = "A MIX disassembler")]
+struct Cli {
+    /// Memory image to disassemble, in text or binary format
+    image: String,
+
+    /// Debug information to take labels from
+    #[arg(short = 'g', long)]
+    debug_info: Option<String>,
+}
+
+fn read(path: &str) -> Result<Vec<u8>> {
+    fs::read(path).with_context(|| format!("Failed to read {}", path))
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let image = Image::load(&read(&cli.image)?)?;
+    let debug_info = cli
+        .debug_info
+        .map(|path| -> Result<DebugInfo> { String::from_utf8(read(&path)?)?.parse() })
+        .transpose()?;
+
+    print!("{}", disassemble(&image, debug_info.as_ref()));
+    Ok(())
+}
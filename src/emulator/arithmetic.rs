@@ -0,0 +1,290 @@
+use anyhow::Result;
+
+use crate::word::{BYTES, Sign, Word};
+
+use super::machine::Machine;
+
+impl Machine {
+    /// B^5, one more than the largest magnitude a word can hold
+    fn word_size(&self) -> u128 {
+        (self.byte_size() as u128).pow(BYTES as u32)
+    }
+
+    /// A word holding a magnitude already known to fit in five bytes
+    fn word(&self, sign: Sign, magnitude: u128) -> Word {
+        Word::from_magnitude(sign, magnitude as u64, BYTES, self.byte_size()).unwrap()
+    }
+
+    /// Adds a value to a register, as ADD does to rA: if the sum doesn't fit in a
+    /// word only its low five bytes are kept and the second value is true, and a
+    /// zero sum keeps the sign of the register.
+    pub(super) fn sum(&self, register: Word, value: i64) -> (Word, bool) {
+        let sum = register.value(self.byte_size()) + value;
+        let magnitude = sum.unsigned_abs() as u128;
+        let sign = if sum == 0 {
+            register.sign
+        } else {
+            Sign::of(sum)
+        };
+        (
+            self.word(sign, magnitude % self.word_size()),
+            magnitude >= self.word_size(),
+        )
+    }
+
+    /// ADD and SUB, setting the overflow toggle if the sum doesn't fit in rA
+    pub(super) fn add(&mut self, m: i64, f: u8, subtract: bool) -> Result<()> {
+        let mut operand = self.operand(m, f)?;
+        if subtract {
+            operand.sign = operand.sign.flip();
+        }
+        let (sum, overflow) = self.sum(self.a, operand.value(self.byte_size()));
+        self.a = sum;
+        self.overflow |= overflow;
+        Ok(())
+    }
+
+    /// MUL puts the ten-byte product of rA and the operand in rAX, with the sign
+    /// of the product in both registers, even when it is zero
+    pub(super) fn multiply(&mut self, m: i64, f: u8) -> Result<()> {
+        let operand = self.operand(m, f)?;
+        let product = self.a.magnitude(self.byte_size()) as u128
+            * operand.magnitude(self.byte_size()) as u128;
+        let sign = if self.a.sign == operand.sign {
+            Sign::Plus
+        } else {
+            Sign::Minus
+        };
+        self.a = self.word(sign, product / self.word_size());
+        self.x = self.word(sign, product % self.word_size());
+        Ok(())
+    }
+
+    /// DIV divides the ten-byte rAX, with the sign of rA, by the operand, leaving
+    /// the quotient in rA and the remainder in rX with the old sign of rA. If the
+    /// operand is zero or the quotient wouldn't fit in rA, the overflow toggle is
+    /// set instead and rA and rX are left as they were.
+    pub(super) fn divide(&mut self, m: i64, f: u8) -> Result<()> {
+        let operand = self.operand(m, f)?;
+        let divisor = operand.magnitude(self.byte_size()) as u128;
+        let high = self.a.magnitude(self.byte_size()) as u128;
+        if divisor == 0 || high >= divisor {
+            self.overflow = true;
+            return Ok(());
+        }
+        let dividend = high * self.word_size() + self.x.magnitude(self.byte_size()) as u128;
+        let sign = if self.a.sign == operand.sign {
+            Sign::Plus
+        } else {
+            Sign::Minus
+        };
+        let remainder_sign = self.a.sign;
+        self.a = self.word(sign, dividend / divisor);
+        self.x = self.word(remainder_sign, dividend % divisor);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// A machine with 100-valued bytes, so that the decimal examples in TAOCP
+    /// come out byte for byte
+    fn decimal() -> Machine {
+        Machine::new(100).unwrap()
+    }
+
+    #[test]
+    fn test_add_examples() {
+        // TAOCP Vol. I, p. 132
+        let mut machine = decimal();
+        machine.a = Word::new(Sign::Plus, [12, 34, 1, 1, 50]);
+        machine.memory[10] = Word::new(Sign::Plus, [1, 0, 5, 0, 50]);
+        machine.execute_statement("ADD 10").unwrap();
+        assert_eq!(machine.a, Word::new(Sign::Plus, [13, 34, 6, 2, 0]));
+
+        let mut machine = decimal();
+        machine.a = Word::new(Sign::Minus, [12, 34, 0, 0, 9]);
+        machine.memory[10] = Word::new(Sign::Minus, [20, 0, 1, 50, 0]);
+        machine.execute_statement("SUB 10").unwrap();
+        assert_eq!(machine.a, Word::new(Sign::Plus, [7, 66, 1, 49, 91]));
+        assert!(!machine.overflow);
+    }
+
+    #[test]
+    fn test_mul_examples() {
+        // TAOCP Vol. I, p. 132
+        let mut machine = decimal();
+        machine.a = Word::new(Sign::Plus, [1, 1, 1, 1, 1]);
+        machine.memory[10] = machine.a;
+        machine.execute_statement("MUL 10").unwrap();
+        assert_eq!(machine.a, Word::new(Sign::Plus, [0, 1, 2, 3, 4]));
+        assert_eq!(machine.x, Word::new(Sign::Plus, [5, 4, 3, 2, 1]));
+
+        let mut machine = decimal();
+        machine.a = Word::new(Sign::Minus, [0, 0, 0, 1, 12]);
+        machine.memory[10] = Word::new(Sign::Plus, [2, 9, 9, 9, 9]);
+        machine.execute_statement("MUL 10(1:1)").unwrap();
+        assert_eq!(machine.a, Word::new(Sign::Minus, [0, 0, 0, 0, 0]));
+        assert_eq!(machine.x, Word::new(Sign::Minus, [0, 0, 0, 2, 24]));
+
+        let mut machine = decimal();
+        machine.a = Word::new(Sign::Minus, [50, 0, 1, 12, 4]);
+        machine.memory[10] = Word::new(Sign::Minus, [2, 0, 0, 0, 0]);
+        machine.execute_statement("MUL 10").unwrap();
+        assert_eq!(machine.a, Word::new(Sign::Plus, [1, 0, 0, 2, 24]));
+        assert_eq!(machine.x, Word::new(Sign::Plus, [8, 0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_div_examples() {
+        // TAOCP Vol. I, p. 133
+        let mut machine = decimal();
+        machine.x = Word::new(Sign::Minus, [0, 0, 0, 0, 17]);
+        machine.memory[10] = Word::new(Sign::Plus, [0, 0, 0, 0, 3]);
+        machine.execute_statement("DIV 10").unwrap();
+        assert_eq!(machine.a, Word::new(Sign::Plus, [0, 0, 0, 0, 5]));
+        assert_eq!(machine.x, Word::new(Sign::Plus, [0, 0, 0, 0, 2]));
+
+        let mut machine = decimal();
+        machine.a = Word::new(Sign::Minus, [0, 0, 0, 0, 0]);
+        machine.x = Word::new(Sign::Plus, [12, 35, 0, 3, 1]);
+        machine.memory[10] = Word::new(Sign::Minus, [0, 0, 0, 2, 0]);
+        machine.execute_statement("DIV 10").unwrap();
+        assert_eq!(machine.a, Word::new(Sign::Plus, [0, 6, 17, 50, 1]));
+        assert_eq!(machine.x, Word::new(Sign::Minus, [0, 0, 0, 1, 1]));
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut machine = Machine::default();
+        machine.a = Word::new(Sign::Plus, [63, 63, 63, 63, 63]);
+        machine.memory[10] = Word::new(Sign::Plus, [0, 0, 0, 0, 1]);
+        machine.execute_statement("ADD 10").unwrap();
+        assert!(machine.overflow);
+        assert_eq!(machine.a, Word::ZERO);
+
+        // Division by zero, and a quotient too large for rA
+        for (a, divisor) in [(0, 0), (5, 5), (6, 5)] {
+            let mut machine = Machine::default();
+            machine.a = Word::from_value(a, 64).unwrap();
+            machine.x = Word::from_value(7, 64).unwrap();
+            machine.memory[10] = Word::from_value(divisor, 64).unwrap();
+            machine.execute_statement("DIV 10").unwrap();
+            assert!(machine.overflow);
+            assert_eq!(machine.a.value(64), a);
+            assert_eq!(machine.x.value(64), 7);
+        }
+    }
+
+    #[test]
+    fn test_zero_signs() {
+        // A zero sum keeps the sign of rA
+        let mut machine = Machine::default();
+        machine.a = Word::from_value(-5, 64).unwrap();
+        machine.memory[10] = Word::from_value(-5, 64).unwrap();
+        machine.execute_statement("SUB 10").unwrap();
+        assert!(machine.a.is_minus_zero());
+        machine.execute_statement("ADD 10(1:5)").unwrap();
+        assert_eq!(machine.a.value(64), 5);
+        machine.execute_statement("SUB 10(1:5)").unwrap();
+        assert_eq!(machine.a, Word::ZERO);
+
+        // A zero product takes the sign of the product
+        machine.memory[10] = Word::from_value(-5, 64).unwrap();
+        machine.execute_statement("MUL 10").unwrap();
+        assert!(machine.a.is_minus_zero());
+        assert!(machine.x.is_minus_zero());
+    }
+
+    fn arbitrary_word(byte_size: i64) -> impl Strategy<Value = Word> {
+        (any::<bool>(), prop::array::uniform5(0..byte_size as u8)).prop_map(|(minus, bytes)| {
+            let sign = if minus { Sign::Minus } else { Sign::Plus };
+            Word::new(sign, bytes)
+        })
+    }
+
+    /// Machines with either byte size, holding rA, rX and the operand at location 10
+    fn arbitrary_machine() -> impl Strategy<Value = Machine> {
+        prop_oneof![Just(64i64), Just(100i64)].prop_flat_map(|byte_size| {
+            (
+                arbitrary_word(byte_size),
+                arbitrary_word(byte_size),
+                arbitrary_word(byte_size),
+            )
+                .prop_map(move |(a, x, operand)| {
+                    let mut machine = Machine::new(byte_size).unwrap();
+                    machine.a = a;
+                    machine.x = x;
+                    machine.memory[10] = operand;
+                    machine
+                })
+        })
+    }
+
+    /// The signed value of a word, as a big integer
+    fn value(word: &Word, byte_size: i64) -> i128 {
+        word.value(byte_size) as i128
+    }
+
+    proptest! {
+        #[test]
+        fn prop_add_sub(machine in arbitrary_machine(), subtract in any::<bool>()) {
+            let b = machine.byte_size();
+            let word_size = (b as i128).pow(5);
+            let operand = value(&machine.memory[10], b);
+            let sum = value(&machine.a, b) + if subtract { -operand } else { operand };
+
+            let mut after = machine.clone();
+            after.execute_statement(if subtract { "SUB 10" } else { "ADD 10" }).unwrap();
+            prop_assert_eq!(after.overflow, sum.abs() >= word_size);
+            prop_assert_eq!(after.a.magnitude(b) as i128, sum.abs() % word_size);
+            if sum != 0 {
+                prop_assert_eq!(after.a.is_negative(), sum < 0);
+            } else {
+                prop_assert_eq!(after.a.sign, machine.a.sign);
+            }
+        }
+
+        #[test]
+        fn prop_mul(machine in arbitrary_machine()) {
+            let b = machine.byte_size();
+            let product = value(&machine.a, b) * value(&machine.memory[10], b);
+
+            let mut after = machine.clone();
+            after.execute_statement("MUL 10").unwrap();
+            let rax = after.a.magnitude(b) as i128 * (b as i128).pow(5)
+                + after.x.magnitude(b) as i128;
+            prop_assert_eq!(rax, product.abs());
+            let negative = machine.a.is_negative() != machine.memory[10].is_negative();
+            prop_assert_eq!(after.a.is_negative(), negative);
+            prop_assert_eq!(after.x.is_negative(), negative);
+        }
+
+        #[test]
+        fn prop_div(machine in arbitrary_machine()) {
+            let b = machine.byte_size();
+            let word_size = (b as i128).pow(5);
+            let divisor = value(&machine.memory[10], b);
+            let magnitude = machine.a.magnitude(b) as i128 * word_size
+                + machine.x.magnitude(b) as i128;
+            let dividend = if machine.a.is_negative() { -magnitude } else { magnitude };
+
+            let mut after = machine.clone();
+            after.execute_statement("DIV 10").unwrap();
+            if divisor == 0 || (dividend / divisor).abs() >= word_size {
+                prop_assert!(after.overflow);
+            } else {
+                prop_assert!(!after.overflow);
+                prop_assert_eq!(value(&after.a, b), dividend / divisor);
+                prop_assert_eq!(value(&after.x, b), dividend % divisor);
+                let negative = machine.a.is_negative() != machine.memory[10].is_negative();
+                prop_assert_eq!(after.a.is_negative(), negative);
+                prop_assert_eq!(after.x.sign, machine.a.sign);
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use anyhow::{Result, bail};
+
+use crate::word::{BYTES, Word};
+
+use super::machine::Machine;
+
+/// The number of bits in the bytes of a binary MIX
+const BITS: u32 = 6;
+
+impl Machine {
+    /// Faults unless this is a binary MIX, the only kind with bits to shift and test.
+    /// The assembler never reports this, as it always assembles for 64-valued bytes;
+    /// only a machine created with another byte size can run into it.
+    fn require_binary(&self, mnemonic: &str) -> Result<()> {
+        if self.byte_size() != 64 {
+            bail!(
+                "{} needs a binary MIX with 64-valued bytes, not {}-valued ones",
+                mnemonic,
+                self.byte_size()
+            );
+        }
+        Ok(())
+    }
+
+    /// SLB and SRB shift rAX, as one 60-bit register, by M bits. The signs of the
+    /// registers never change.
+    pub(super) fn binary_shift(&mut self, m: i64, f: u8) -> Result<()> {
+        let left = f == 6;
+        self.require_binary(if left { "SLB" } else { "SRB" })?;
+        if m < 0 {
+            bail!("Shift count {} is negative", m);
+        }
+        let width = 2 * BYTES as u32 * BITS;
+        let half = BYTES as u32 * BITS;
+        let ax = ((self.a.magnitude(64) as u64) << half) | self.x.magnitude(64) as u64;
+        let shifted = match m {
+            m if m >= width as i64 => 0,
+            m if left => (ax << m) & ((1 << width) - 1),
+            m => ax >> m,
+        };
+        let mask = (1 << half) - 1;
+        self.a = Word::from_magnitude(self.a.sign, shifted >> half, BYTES, 64)?;
+        self.x = Word::from_magnitude(self.x.sign, shifted & mask, BYTES, 64)?;
+        Ok(())
+    }
+
+    /// JAE, JAO, JXE and JXO jump if rA or rX (register 0 or 7) is even (F = 6) or
+    /// odd (F = 7)
+    pub(super) fn jump_parity(&mut self, r: u8, m: i64, f: u8) -> Result<()> {
+        let mnemonic = match (r, f) {
+            (0, 6) => "JAE",
+            (0, _) => "JAO",
+            (_, 6) => "JXE",
+            _ => "JXO",
+        };
+        self.require_binary(mnemonic)?;
+        let odd = self.register(r).bytes[BYTES - 1] % 2 == 1;
+        if odd == (f == 7) {
+            self.jump_to(m, true)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word::Sign;
+
+    #[test]
+    fn test_binary_shifts() {
+        let mut machine = Machine::default();
+        machine.a = Word::new(Sign::Minus, [0, 0, 0, 0, 1]);
+        machine.x = Word::new(Sign::Plus, [32, 0, 0, 0, 3]);
+        // The top bit of rX moves into rA
+        machine.execute_statement("SLB 1").unwrap();
+        assert_eq!(machine.a, Word::new(Sign::Minus, [0, 0, 0, 0, 3]));
+        assert_eq!(machine.x, Word::new(Sign::Plus, [0, 0, 0, 0, 6]));
+        machine.execute_statement("SRB 7").unwrap();
+        assert!(machine.a.is_minus_zero());
+        assert_eq!(machine.x, Word::new(Sign::Plus, [1, 32, 0, 0, 0]));
+        machine.execute_statement("SLB 60").unwrap();
+        assert_eq!(machine.x, Word::ZERO);
+
+        machine.i[0] = Word::from_index(-1, 64).unwrap();
+        assert!(machine.execute_statement("SRB 0,1").is_err());
+    }
+
+    #[test]
+    fn test_parity_jumps() {
+        let examples = [
+            ("JAE", 4, true),
+            ("JAO", 4, false),
+            ("JAO", -7, true),
+            ("JXE", 0, true),
+            ("JXO", 1, true),
+            ("JXE", 1, false),
+        ];
+        for (jump, value, taken) in examples {
+            let mut machine = Machine::default();
+            machine.a = Word::from_value(value, 64).unwrap();
+            machine.x = machine.a;
+            machine
+                .execute_statement(&format!("{} 2000", jump))
+                .unwrap();
+            assert_eq!(machine.pc == 2000, taken, "{} on {}", jump, value);
+            assert_eq!(machine.j.value(64), if taken { 1 } else { 0 });
+        }
+    }
+
+    #[test]
+    fn test_decimal_machine() {
+        let mut machine = Machine::new(100).unwrap();
+        let fault = machine.execute_statement("SLB 1").unwrap_err();
+        assert_eq!(
+            fault.message,
+            "SLB needs a binary MIX with 64-valued bytes, not 100-valued ones"
+        );
+        assert!(machine.execute_statement("JAE 10").is_err());
+        assert!(machine.execute_statement("JXO 10").is_err());
+        assert_eq!(machine.pc, 0);
+    }
+}
@@ -0,0 +1,67 @@
+use std::cmp::Ordering;
+
+use anyhow::Result;
+
+use crate::word::FieldSpec;
+
+use super::machine::{Comparison, Machine};
+
+impl Machine {
+    /// CMPA, CMPX and CMPi compare the field F of register `r` with the field F of
+    /// location M as signed numbers, so -0 equals +0, and a field without the sign
+    /// compares as positive
+    pub(super) fn compare(&mut self, r: u8, m: i64, f: u8) -> Result<()> {
+        let operand = self.operand(m, f)?.value(self.byte_size());
+        let field = FieldSpec::from_byte(f)?;
+        let register = self.register(r).field(field).value(self.byte_size());
+        self.comparison = match register.cmp(&operand) {
+            Ordering::Less => Comparison::Less,
+            Ordering::Equal => Comparison::Equal,
+            Ordering::Greater => Comparison::Greater,
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word::{Sign, Word};
+
+    #[test]
+    fn test_compare() {
+        let mut machine = Machine::default();
+        machine.memory[1000] = Word::new(Sign::Minus, [1, 2, 3, 4, 5]);
+        machine.a = Word::new(Sign::Plus, [1, 2, 3, 4, 6]);
+        let examples = [
+            ("CMPA 1000", Comparison::Greater),
+            ("CMPA 1000(1:5)", Comparison::Greater),
+            ("CMPA 1000(1:4)", Comparison::Equal),
+            ("CMPA 1000(0:4)", Comparison::Greater),
+            ("CMPA 1000(5:5)", Comparison::Greater),
+            ("CMPX 1000", Comparison::Greater),
+            ("CMPX 1000(1:5)", Comparison::Less),
+            ("CMP1 1000(4:5)", Comparison::Less),
+        ];
+        for (statement, expected) in examples {
+            machine.execute_statement(statement).unwrap();
+            assert_eq!(machine.comparison, expected, "{}", statement);
+        }
+    }
+
+    #[test]
+    fn test_compare_zero() {
+        // -0 equals +0, and (0:0) compares two zeros however the signs differ
+        let mut machine = Machine::default();
+        machine.memory[1000] = Word::new(Sign::Minus, [0; 5]);
+        machine.execute_statement("CMPA 1000").unwrap();
+        assert_eq!(machine.comparison, Comparison::Equal);
+
+        machine.memory[1000] = Word::new(Sign::Minus, [9, 9, 9, 9, 9]);
+        machine.i[3] = Word::from_index(5, 64).unwrap();
+        machine.execute_statement("CMP4 1000").unwrap();
+        assert_eq!(machine.comparison, Comparison::Greater);
+        machine.execute_statement("CMP4 1000(0:0)").unwrap();
+        assert_eq!(machine.comparison, Comparison::Equal);
+    }
+}
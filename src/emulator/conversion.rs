@@ -0,0 +1,82 @@
+use crate::word::{BYTES, Word};
+
+use super::machine::Machine;
+
+/// The character code of the digit 0; the codes of 1 to 9 follow it
+const ZERO: u8 = 30;
+
+impl Machine {
+    /// NUM reads the ten bytes of rAX as decimal digits, each byte giving the digit
+    /// it is congruent to modulo 10, and sets the magnitude of rA to the number. If
+    /// it doesn't fit, the overflow toggle is set and the remainder modulo B^5 is
+    /// kept. The sign of rA and all of rX are unchanged.
+    pub(super) fn num(&mut self) {
+        let digits = self.a.bytes.iter().chain(&self.x.bytes);
+        let value = digits.fold(0u64, |acc, &byte| acc * 10 + (byte % 10) as u64);
+        let word_size = (self.byte_size() as u64).pow(BYTES as u32);
+        if value >= word_size {
+            self.overflow = true;
+        }
+        self.a =
+            Word::from_magnitude(self.a.sign, value % word_size, BYTES, self.byte_size()).unwrap();
+    }
+
+    /// CHAR writes the magnitude of rA as ten decimal digits in character code
+    /// across rAX, leaving the signs of both registers unchanged
+    pub(super) fn char(&mut self) {
+        let mut value = self.a.magnitude(self.byte_size());
+        let mut digits = [0; 2 * BYTES];
+        for digit in digits.iter_mut().rev() {
+            *digit = ZERO + (value % 10) as u8;
+            value /= 10;
+        }
+        self.a.bytes.copy_from_slice(&digits[..BYTES]);
+        self.x.bytes.copy_from_slice(&digits[BYTES..]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word::Sign;
+
+    #[test]
+    fn test_conversion_example() {
+        // TAOCP Vol. I, p. 135
+        let mut machine = Machine::default();
+        machine.a = Word::new(Sign::Minus, [0, 0, 31, 32, 39]);
+        machine.x = Word::new(Sign::Plus, [37, 57, 47, 30, 30]);
+        machine.execute_statement("NUM 0").unwrap();
+        assert_eq!(machine.a.value(64), -12977700);
+        assert_eq!(machine.x, Word::new(Sign::Plus, [37, 57, 47, 30, 30]));
+        machine.execute_statement("INCA 1").unwrap();
+        machine.execute_statement("CHAR 0").unwrap();
+        assert_eq!(machine.a, Word::new(Sign::Minus, [30, 30, 31, 32, 39]));
+        assert_eq!(machine.x, Word::new(Sign::Plus, [37, 37, 36, 39, 39]));
+        assert!(!machine.overflow);
+    }
+
+    #[test]
+    fn test_num_overflow() {
+        // 9999999999 is more than 64^5 - 1, but fits with 100-valued bytes
+        for (byte_size, overflow) in [(64, true), (100, false)] {
+            let mut machine = Machine::new(byte_size).unwrap();
+            machine.a = Word::new(Sign::Plus, [39; 5]);
+            machine.x = Word::new(Sign::Plus, [39; 5]);
+            machine.execute_statement("NUM 0").unwrap();
+            assert_eq!(machine.overflow, overflow);
+            let expected = 9_999_999_999 % byte_size.pow(5);
+            assert_eq!(machine.a.value(byte_size), expected);
+        }
+    }
+
+    #[test]
+    fn test_char_largest() {
+        let mut machine = Machine::default();
+        machine.a = Word::new(Sign::Plus, [63; 5]);
+        machine.execute_statement("CHAR 0").unwrap();
+        // 64^5 - 1 = 1073741823
+        assert_eq!(machine.a, Word::new(Sign::Plus, [31, 30, 37, 33, 37]));
+        assert_eq!(machine.x, Word::new(Sign::Plus, [34, 31, 38, 32, 33]));
+    }
+}
@@ -0,0 +1,552 @@
+use anyhow::Result;
+
+use crate::word::{BYTES, Sign, Word};
+
+use super::machine::{Comparison, Machine};
+
+/// p, the number of digits in the fraction of a floating point word
+const DIGITS: u32 = 4;
+
+/// The number of fraction digits FDIV works out before rounding
+const QUOTIENT_DIGITS: u32 = 10;
+
+/// A floating point number that may not be normalized, worth
+/// ±fraction × b^(exponent - q - scale). Words unpack with a scale of 4, i.e. as
+/// ±.ffff × b^(e - q).
+#[derive(Debug, Clone, Copy)]
+struct Float {
+    sign: Sign,
+    exponent: i64,
+    fraction: u128,
+    scale: u32,
+}
+
+impl Float {
+    fn negated(self) -> Self {
+        Float {
+            sign: self.sign.flip(),
+            ..self
+        }
+    }
+}
+
+/// The sign of a product or quotient
+fn product_sign(u: Sign, v: Sign) -> Sign {
+    if u == v { Sign::Plus } else { Sign::Minus }
+}
+
+impl Machine {
+    fn base(&self) -> u128 {
+        self.byte_size() as u128
+    }
+
+    /// q, the excess of the exponent
+    fn excess(&self) -> i64 {
+        self.byte_size() / 2
+    }
+
+    /// Takes apart a word ± e f f f f
+    fn unpack(&self, word: &Word) -> Float {
+        let fraction = word.bytes[1..]
+            .iter()
+            .fold(0, |acc, &b| acc * self.base() + b as u128);
+        Float {
+            sign: word.sign,
+            exponent: word.bytes[0] as i64,
+            fraction,
+            scale: DIGITS,
+        }
+    }
+
+    /// The number of base-b digits in `n`
+    fn digits(&self, mut n: u128) -> u32 {
+        let mut digits = 0;
+        while n > 0 {
+            n /= self.base();
+            digits += 1;
+        }
+        digits
+    }
+
+    /// Rounds n / b^places to the nearest integer. A tie goes to the neighbor r for
+    /// which r + b/2 is odd, as in step N5 of TAOCP Vol. II, p. 215.
+    fn round(&self, n: u128, places: u32) -> u128 {
+        let unit = self.base().pow(places);
+        let (quotient, remainder) = (n / unit, n % unit);
+        let tie = 2 * remainder == unit && (quotient + self.base() / 2).is_multiple_of(2);
+        if 2 * remainder > unit || tie {
+            quotient + 1
+        } else {
+            quotient
+        }
+    }
+
+    /// Normalizes and rounds a value into a word, following Algorithm N. The second
+    /// value is true on exponent overflow or underflow, in which case the exponent
+    /// is kept modulo b.
+    fn pack(&self, value: Float) -> (Word, bool) {
+        if value.fraction == 0 {
+            return (Word::new(value.sign, [0; BYTES]), false);
+        }
+        let digits = self.digits(value.fraction);
+        let mut exponent = value.exponent + digits as i64 - value.scale as i64;
+        let mut fraction = if digits > DIGITS {
+            self.round(value.fraction, digits - DIGITS)
+        } else {
+            value.fraction * self.base().pow(DIGITS - digits)
+        };
+        if fraction == self.base().pow(DIGITS) {
+            // Rounding carried into a fifth digit
+            fraction /= self.base();
+            exponent += 1;
+        }
+        let mut word = Word::from_magnitude(
+            value.sign,
+            fraction as u64,
+            DIGITS as usize,
+            self.byte_size(),
+        )
+        .unwrap();
+        word.bytes[0] = exponent.rem_euclid(self.byte_size()) as u8;
+        (word, !(0..self.byte_size()).contains(&exponent))
+    }
+
+    /// Packs a value into rA, setting the overflow toggle on exponent overflow
+    fn set_float(&mut self, value: Float) {
+        let (word, overflow) = self.pack(value);
+        self.a = word;
+        self.overflow |= overflow;
+    }
+
+    /// u + v by Algorithm A: the operand with the smaller exponent, or u if they
+    /// are equal, is scaled to the exponent of the other one, and ignored if that
+    /// would take it more than p + 1 places to the right. An exact zero keeps the
+    /// sign of the scaled operand, as ADD keeps the sign of rA in step A6.
+    fn float_sum(&self, u: Float, v: Float) -> Float {
+        let (u, v) = if u.exponent <= v.exponent {
+            (v, u)
+        } else {
+            (u, v)
+        };
+        let shift = (u.exponent - v.exponent) as u32;
+        if shift >= DIGITS + 2 {
+            return u;
+        }
+        let scaled = u.fraction * self.base().pow(shift);
+        let (sign, fraction) = if u.sign == v.sign {
+            (u.sign, scaled + v.fraction)
+        } else if scaled > v.fraction {
+            (u.sign, scaled - v.fraction)
+        } else {
+            (v.sign, v.fraction - scaled)
+        };
+        Float {
+            sign,
+            exponent: u.exponent,
+            fraction,
+            scale: u.scale + shift,
+        }
+    }
+
+    /// u × v by Algorithm M, keeping all eight digits of the product for rounding
+    fn float_product(&self, u: Float, v: Float) -> Float {
+        Float {
+            sign: product_sign(u.sign, v.sign),
+            exponent: u.exponent + v.exponent - self.excess(),
+            fraction: u.fraction * v.fraction,
+            scale: u.scale + v.scale,
+        }
+    }
+
+    /// u / v, or None if v is zero. Digits past the ones worked out are summed up
+    /// by a final 1 if the division isn't exact, which is all rounding needs.
+    fn float_quotient(&self, u: Float, v: Float) -> Option<Float> {
+        if v.fraction == 0 {
+            return None;
+        }
+        let dividend = u.fraction * self.base().pow(QUOTIENT_DIGITS);
+        let mut fraction = dividend / v.fraction;
+        let mut scale = QUOTIENT_DIGITS + u.scale - v.scale;
+        if !dividend.is_multiple_of(v.fraction) {
+            fraction = fraction * self.base() + 1;
+            scale += 1;
+        }
+        Some(Float {
+            sign: product_sign(u.sign, v.sign),
+            exponent: u.exponent - v.exponent + self.excess(),
+            fraction,
+            scale,
+        })
+    }
+
+    /// FADD, FSUB, FMUL and FDIV (C = 1 to 4) of rA and location M. Division by
+    /// zero sets the overflow toggle and leaves rA as it was.
+    pub(super) fn float_arithmetic(&mut self, c: u8, m: i64) -> Result<()> {
+        let u = self.unpack(&self.a);
+        let v = self.unpack(&self.memory[self.location(m)?]);
+        let result = match c {
+            1 => self.float_sum(u, v),
+            2 => self.float_sum(u, v.negated()),
+            3 => self.float_product(u, v),
+            _ => match self.float_quotient(u, v) {
+                Some(quotient) => quotient,
+                None => {
+                    self.overflow = true;
+                    return Ok(());
+                }
+            },
+        };
+        self.set_float(result);
+        Ok(())
+    }
+
+    /// FLOT converts the integer in rA to floating point, rounding it if it has
+    /// more than four digits
+    pub(super) fn flot(&mut self) {
+        self.set_float(Float {
+            sign: self.a.sign,
+            exponent: self.excess(),
+            fraction: self.a.magnitude(self.byte_size()) as u128,
+            scale: 0,
+        });
+    }
+
+    /// FIX converts the floating point number in rA to the nearest integer, rounding
+    /// ties like normalization does. If it doesn't fit, the overflow toggle is set
+    /// and the low five bytes are kept.
+    pub(super) fn fix(&mut self) {
+        let u = self.unpack(&self.a);
+        let shift = u.exponent - self.excess() - DIGITS as i64;
+        let magnitude = if u.fraction == 0 || shift < -(DIGITS as i64 + 1) {
+            0
+        } else if shift < 0 {
+            self.round(u.fraction, -shift as u32)
+        } else if shift < BYTES as i64 {
+            u.fraction * self.base().pow(shift as u32)
+        } else {
+            // A multiple of b^5, so it overflows and leaves zero
+            self.overflow = true;
+            0
+        };
+        let word_size = self.base().pow(BYTES as u32);
+        if magnitude >= word_size {
+            self.overflow = true;
+        }
+        self.a = Word::from_magnitude(
+            u.sign,
+            (magnitude % word_size) as u64,
+            BYTES,
+            self.byte_size(),
+        )
+        .unwrap();
+    }
+
+    /// Whether a × b^x > c × b^y, for a and c less than b^4
+    fn exceeds(&self, a: u128, x: i64, c: u128, y: i64) -> bool {
+        let places = x - y;
+        if a == 0 {
+            false
+        } else if c == 0 || places > DIGITS as i64 {
+            true
+        } else if places < -(DIGITS as i64) {
+            false
+        } else if places >= 0 {
+            a * self.base().pow(places as u32) > c
+        } else {
+            a > c * self.base().pow(-places as u32)
+        }
+    }
+
+    /// FCMP compares rA with location M the way TAOCP Vol. II, §4.2.2 defines
+    /// approximate comparison, with the ε at location 0: the two are equal if they
+    /// differ by at most ε × b^(max(e_u, e_v) - q). With +0 at location 0 the
+    /// comparison is exact.
+    pub(super) fn float_compare(&mut self, m: i64) -> Result<()> {
+        let u = self.unpack(&self.a);
+        let v = self.unpack(&self.memory[self.location(m)?]);
+        let epsilon = self.unpack(&self.memory[0]);
+        let (difference, _) = self.pack(self.float_sum(u, v.negated()));
+        let difference = self.unpack(&difference);
+        let limit_exponent = epsilon.exponent + u.exponent.max(v.exponent) - self.excess();
+        let approximately_equal = !self.exceeds(
+            difference.fraction,
+            difference.exponent,
+            epsilon.fraction,
+            limit_exponent,
+        );
+        self.comparison = if approximately_equal {
+            Comparison::Equal
+        } else if difference.sign == Sign::Minus {
+            Comparison::Less
+        } else {
+            Comparison::Greater
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn float(exponent: u8, fraction: [u8; 4]) -> Word {
+        let [f1, f2, f3, f4] = fraction;
+        Word::new(Sign::Plus, [exponent, f1, f2, f3, f4])
+    }
+
+    fn negative(word: Word) -> Word {
+        Word::new(Sign::Minus, word.bytes)
+    }
+
+    /// The value of a floating point word
+    fn value(word: &Word, byte_size: i64) -> f64 {
+        let b = byte_size as f64;
+        let fraction = word.bytes[1..]
+            .iter()
+            .rev()
+            .fold(0.0, |acc, &d| (acc + d as f64) / b);
+        let magnitude = fraction * b.powi(word.bytes[0] as i32 - byte_size as i32 / 2);
+        if word.is_negative() {
+            -magnitude
+        } else {
+            magnitude
+        }
+    }
+
+    /// Executes `statement` with rA = u and location 1000 = v
+    fn execute(statement: &str, u: Word, v: Word) -> Machine {
+        let mut machine = Machine::default();
+        machine.a = u;
+        machine.memory[1000] = v;
+        machine.execute_statement(statement).unwrap();
+        machine
+    }
+
+    const ONE: [u8; 4] = [1, 0, 0, 0];
+
+    #[test]
+    fn test_add() {
+        let one = float(33, ONE);
+        assert_eq!(execute("FADD 1000", one, one).a, float(33, [2, 0, 0, 0]));
+
+        // Cancellation takes a left shift in step N3, and an exact zero keeps the sign
+        // of rA
+        let almost_one = float(32, [63, 0, 0, 0]);
+        let machine = execute("FSUB 1000", one, almost_one);
+        assert_eq!(machine.a, float(32, ONE));
+        assert_eq!(
+            execute("FADD 1000", almost_one, negative(almost_one)).a,
+            Word::ZERO
+        );
+        assert!(
+            execute("FADD 1000", negative(almost_one), almost_one)
+                .a
+                .is_minus_zero()
+        );
+
+        // A carry out of the fraction takes a right shift in step N4
+        let half = float(32, [32, 0, 0, 0]);
+        assert_eq!(execute("FADD 1000", half, one).a, float(33, [1, 32, 0, 0]));
+        assert_eq!(execute("FADD 1000", one, half).a, float(33, [1, 32, 0, 0]));
+        let large = float(33, [63, 0, 0, 0]);
+        assert_eq!(execute("FADD 1000", large, one).a, float(34, [1, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_add_rounding() {
+        let one = float(33, ONE);
+        // Exactly half a unit in the last place: rounds to an odd last digit
+        let half_unit = float(29, [32, 0, 0, 0]);
+        assert_eq!(
+            execute("FADD 1000", one, half_unit).a,
+            float(33, [1, 0, 0, 1])
+        );
+        let odd = float(33, [1, 0, 0, 1]);
+        assert_eq!(execute("FADD 1000", odd, half_unit).a, odd);
+
+        // More than half rounds up, which can carry all the way into the exponent (N6)
+        let most = float(33, [63, 63, 63, 63]);
+        let over_half = float(29, [33, 0, 0, 0]);
+        assert_eq!(execute("FADD 1000", most, over_half).a, float(34, ONE));
+
+        // Step A3 ignores v when it is p + 2 or more places to the right
+        let tiny = float(27, [63, 63, 63, 63]);
+        assert_eq!(execute("FADD 1000", one, tiny).a, one);
+        assert_eq!(execute("FSUB 1000", tiny, one).a, negative(one));
+    }
+
+    #[test]
+    fn test_multiply_divide() {
+        let one_and_half = float(33, [1, 32, 0, 0]);
+        let two = float(33, [2, 0, 0, 0]);
+        assert_eq!(
+            execute("FMUL 1000", one_and_half, negative(two)).a,
+            negative(float(33, [3, 0, 0, 0]))
+        );
+        assert_eq!(
+            execute("FDIV 1000", two, one_and_half).a,
+            float(33, [1, 21, 21, 21])
+        );
+
+        let three = float(33, [3, 0, 0, 0]);
+        let one = float(33, ONE);
+        assert_eq!(
+            execute("FDIV 1000", one, three).a,
+            float(32, [21, 21, 21, 21])
+        );
+        assert_eq!(
+            execute("FDIV 1000", two, three).a,
+            float(32, [42, 42, 42, 43])
+        );
+
+        let machine = execute("FDIV 1000", one, Word::ZERO);
+        assert!(machine.overflow);
+        assert_eq!(machine.a, one);
+    }
+
+    #[test]
+    fn test_exponent_overflow() {
+        let huge = float(63, ONE);
+        let machine = execute("FMUL 1000", huge, huge);
+        assert!(machine.overflow);
+        // 63 + 63 - 32 - 1 = 93, which is 29 modulo 64
+        assert_eq!(machine.a, float(29, ONE));
+
+        let tiny = float(1, ONE);
+        let machine = execute("FMUL 1000", tiny, tiny);
+        assert!(machine.overflow);
+        // The exponent works out to 1 + 1 - 32 - 1 = -31, which is 33 modulo 64
+        assert_eq!(machine.a, float(33, ONE));
+
+        let machine = execute("FADD 1000", float(33, ONE), float(33, ONE));
+        assert!(!machine.overflow);
+    }
+
+    #[test]
+    fn test_flot_fix() {
+        let mut machine = Machine::default();
+        machine.execute_statement("ENTA -100").unwrap();
+        machine.execute_statement("FLOT").unwrap();
+        assert_eq!(machine.a, negative(float(34, [1, 36, 0, 0])));
+        machine.execute_statement("FIX").unwrap();
+        assert_eq!(machine.a.value(64), -100);
+
+        // Five digits have to be rounded to four
+        machine.a = Word::new(Sign::Plus, [1, 2, 3, 4, 32]);
+        machine.execute_statement("FLOT").unwrap();
+        assert_eq!(machine.a, float(37, [1, 2, 3, 5]));
+        machine.execute_statement("FIX").unwrap();
+        assert_eq!(machine.a, Word::new(Sign::Plus, [1, 2, 3, 5, 0]));
+
+        // 2.5 and 3.5 both round to 3, 2.75 to 3 and 0.25 to 0
+        for (fraction, expected) in [([2, 32, 0, 0], 3), ([3, 32, 0, 0], 3), ([2, 48, 0, 0], 3)] {
+            machine.a = float(33, fraction);
+            machine.execute_statement("FIX").unwrap();
+            assert_eq!(machine.a.value(64), expected);
+        }
+        machine.a = negative(float(32, [16, 0, 0, 0]));
+        machine.execute_statement("FIX").unwrap();
+        assert!(machine.a.is_minus_zero());
+
+        machine.a = float(38, ONE);
+        machine.execute_statement("FIX").unwrap();
+        assert!(machine.overflow);
+        assert_eq!(machine.a, Word::ZERO);
+    }
+
+    #[test]
+    fn test_compare() {
+        let one = float(33, ONE);
+        let more = float(33, [1, 0, 0, 1]);
+        assert_eq!(execute("FCMP 1000", one, more).comparison, Comparison::Less);
+        assert_eq!(
+            execute("FCMP 1000", more, one).comparison,
+            Comparison::Greater
+        );
+        assert_eq!(execute("FCMP 1000", one, one).comparison, Comparison::Equal);
+        assert_eq!(
+            execute("FCMP 1000", Word::ZERO, negative(Word::ZERO)).comparison,
+            Comparison::Equal
+        );
+
+        // With ε = 1.5 b^-4 at location 0, numbers near 1 are equal if they are at
+        // most one unit in the last place apart
+        let mut machine = Machine::default();
+        machine.pc = 100;
+        machine.memory[0] = float(29, [1, 32, 0, 0]);
+        machine.a = one;
+        machine.memory[1000] = more;
+        machine.execute_statement("FCMP 1000").unwrap();
+        assert_eq!(machine.comparison, Comparison::Equal);
+        machine.memory[1000] = float(33, [1, 0, 0, 2]);
+        machine.execute_statement("FCMP 1000").unwrap();
+        assert_eq!(machine.comparison, Comparison::Less);
+        assert_eq!(machine.a, one);
+    }
+
+    /// Normalized floating point words with exponents near q, so that sums and
+    /// products of them are exact as f64
+    fn arbitrary_float(byte_size: i64) -> impl Strategy<Value = Word> {
+        let q = byte_size / 2;
+        (
+            any::<bool>(),
+            (q - 2)..=(q + 2),
+            1..byte_size as u8,
+            prop::array::uniform3(0..byte_size as u8),
+        )
+            .prop_map(|(minus, exponent, first, [f2, f3, f4])| {
+                let sign = if minus { Sign::Minus } else { Sign::Plus };
+                Word::new(sign, [exponent as u8, first, f2, f3, f4])
+            })
+    }
+
+    /// Operands that are often ±u and u, so that sums cancel exactly
+    fn arbitrary_operands() -> impl Strategy<Value = (i64, Word, Word)> {
+        prop_oneof![Just(64i64), Just(100i64)].prop_flat_map(|byte_size| {
+            (
+                Just(byte_size),
+                arbitrary_float(byte_size),
+                arbitrary_float(byte_size),
+                0..4,
+            )
+                .prop_map(|(byte_size, u, v, pick)| match pick {
+                    0 => (byte_size, u, u),
+                    1 => (byte_size, u, Word::new(u.sign.flip(), u.bytes)),
+                    _ => (byte_size, u, v),
+                })
+        })
+    }
+
+    proptest! {
+        /// The result is normalized and within half a unit in its last place of the
+        /// exact value
+        #[test]
+        fn prop_correctly_rounded((byte_size, u, v) in arbitrary_operands(), c in 1u8..=4) {
+            let mut machine = Machine::new(byte_size).unwrap();
+            machine.a = u;
+            // An address below 64 means the same with either byte size
+            machine.memory[10] = v;
+            let op = ["FADD", "FSUB", "FMUL", "FDIV"][c as usize - 1];
+            machine.execute_statement(&format!("{} 10", op)).unwrap();
+
+            let sign = u.sign;
+            let (u, v) = (value(&u, byte_size), value(&v, byte_size));
+            let exact = [u + v, u - v, u * v, u / v][c as usize - 1];
+            let w = machine.a;
+            prop_assert!(!machine.overflow);
+            if exact == 0.0 {
+                // Normalized operands that cancel have the same exponent, so u is
+                // the one scaled in step A5 and the zero keeps its sign
+                prop_assert_eq!(w, Word::new(sign, [0; BYTES]));
+            } else {
+                prop_assert!(w.bytes[1] != 0, "{} is not normalized", w);
+                let q = byte_size as i32 / 2;
+                let unit = (byte_size as f64).powi(w.bytes[0] as i32 - q - 4);
+                let error = (value(&w, byte_size) - exact).abs();
+                prop_assert!(error <= unit / 2.0 + exact.abs() * 1e-14, "{} for {}", w, exact);
+            }
+        }
+    }
+}
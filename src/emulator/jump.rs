@@ -0,0 +1,162 @@
+use anyhow::{Result, bail};
+
+use crate::word::Word;
+
+use super::machine::{Comparison, Machine};
+
+impl Machine {
+    /// Jumps to location M, setting rJ to the location of the instruction after
+    /// the jump unless `save` is false
+    pub(super) fn jump_to(&mut self, m: i64, save: bool) -> Result<()> {
+        let target = self.location(m)?;
+        if save {
+            self.j = Word::from_index(self.pc as i64, self.byte_size())?;
+        }
+        self.pc = target;
+        Ok(())
+    }
+
+    /// The jumps of operation code 39: JMP, JSJ, JOV, JNOV and the jumps on the
+    /// comparison indicator, JL to JLE. JOV and JNOV also turn the overflow
+    /// toggle off.
+    pub(super) fn jump(&mut self, m: i64, f: u8) -> Result<()> {
+        let comparison = self.comparison;
+        let condition = match f {
+            0 | 1 => true,
+            2 => self.overflow,
+            3 => !self.overflow,
+            4 => comparison == Comparison::Less,
+            5 => comparison == Comparison::Equal,
+            6 => comparison == Comparison::Greater,
+            7 => comparison != Comparison::Less,
+            8 => comparison != Comparison::Equal,
+            9 => comparison != Comparison::Greater,
+            _ => bail!("Invalid instruction: C = 39, F = {}", f),
+        };
+        if condition {
+            self.jump_to(m, f != 1)?;
+        }
+        if matches!(f, 2 | 3) {
+            self.overflow = false;
+        }
+        Ok(())
+    }
+
+    /// JrN, JrZ, JrP, JrNN, JrNZ and JrNP (F = 0 to 5) for register `r`, where
+    /// -0 counts as zero
+    pub(super) fn jump_register(&mut self, r: u8, m: i64, f: u8) -> Result<()> {
+        let byte_size = self.byte_size();
+        let value = self.register(r).value(byte_size);
+        let condition = match f {
+            0 => value < 0,
+            1 => value == 0,
+            2 => value > 0,
+            3 => value >= 0,
+            4 => value != 0,
+            _ => value <= 0,
+        };
+        if condition {
+            self.jump_to(m, true)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word::Sign;
+
+    #[test]
+    fn test_jmp_and_jsj() {
+        let mut machine = Machine::default();
+        machine.pc = 100;
+        machine.execute_statement("JMP 2000").unwrap();
+        assert_eq!(machine.pc, 2000);
+        assert_eq!(machine.j.value(64), 101);
+        machine.execute_statement("JSJ 3000").unwrap();
+        assert_eq!(machine.pc, 3000);
+        assert_eq!(machine.j.value(64), 101);
+
+        let fault = machine.execute_statement("JMP 4000").unwrap_err();
+        assert_eq!(fault.message, "Address 4000 is outside of memory");
+        assert_eq!(machine.pc, 3000);
+    }
+
+    #[test]
+    fn test_overflow_jumps() {
+        let mut machine = Machine::default();
+        machine.overflow = true;
+        machine.execute_statement("JNOV 2000").unwrap();
+        assert_eq!(machine.pc, 1);
+        assert!(!machine.overflow);
+        machine.execute_statement("JOV 2000").unwrap();
+        assert_eq!(machine.pc, 2);
+
+        machine.overflow = true;
+        machine.execute_statement("JOV 2000").unwrap();
+        assert_eq!(machine.pc, 2000);
+        assert_eq!(machine.j.value(64), 3);
+        assert!(!machine.overflow);
+    }
+
+    #[test]
+    fn test_comparison_jumps() {
+        let jumps = ["JL", "JE", "JG", "JGE", "JNE", "JLE"];
+        let taken = [
+            (Comparison::Less, [true, false, false, false, true, true]),
+            (Comparison::Equal, [false, true, false, true, false, true]),
+            (Comparison::Greater, [false, false, true, true, true, false]),
+        ];
+        for (comparison, expected) in taken {
+            for (jump, expected) in jumps.iter().zip(expected) {
+                let mut machine = Machine::default();
+                machine.comparison = comparison;
+                machine
+                    .execute_statement(&format!("{} 2000", jump))
+                    .unwrap();
+                assert_eq!(machine.pc == 2000, expected, "{} on {}", jump, comparison);
+            }
+        }
+    }
+
+    #[test]
+    fn test_register_jumps() {
+        let jumps = ["N", "Z", "P", "NN", "NZ", "NP"];
+        let minus_zero = Word::new(Sign::Minus, [0; 5]);
+        let taken = [
+            (
+                Word::from_value(-3, 64).unwrap(),
+                [true, false, false, false, true, true],
+            ),
+            (Word::ZERO, [false, true, false, true, false, true]),
+            (minus_zero, [false, true, false, true, false, true]),
+            (
+                Word::from_value(3, 64).unwrap(),
+                [false, false, true, true, true, false],
+            ),
+        ];
+        for (register, (name, r)) in [("A", 0), ("1", 1), ("6", 6), ("X", 7)]
+            .into_iter()
+            .flat_map(|register| taken.iter().map(move |t| (t, register)))
+        {
+            let (value, expected) = register;
+            for (jump, &expected) in jumps.iter().zip(expected) {
+                let mut machine = Machine::default();
+                *machine.register(r) = *value;
+                machine
+                    .execute_statement(&format!("J{}{} 2000", name, jump))
+                    .unwrap();
+                assert_eq!(
+                    machine.pc == 2000,
+                    expected,
+                    "J{}{} on {}",
+                    name,
+                    jump,
+                    value
+                );
+                assert_eq!(machine.j.value(64), if expected { 1 } else { 0 });
+            }
+        }
+    }
+}
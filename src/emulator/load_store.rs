@@ -0,0 +1,202 @@
+use anyhow::{Result, bail};
+
+use crate::word::{FieldSpec, Word};
+
+use super::machine::Machine;
+
+impl Machine {
+    /// LDA, LDX and LDi load the field F of location M into register `r`, shifted
+    /// right; the N forms load it with the opposite sign. An index register can only
+    /// take a value that fits in two bytes.
+    pub(super) fn load_register(&mut self, r: u8, negate: bool, m: i64, f: u8) -> Result<()> {
+        let mut word = self.operand(m, f)?;
+        if negate {
+            word.sign = word.sign.flip();
+        }
+        if (1..=6).contains(&r) && !word.is_index() {
+            bail!("{} does not fit in index register {}", word, r);
+        }
+        *self.register(r) = word;
+        Ok(())
+    }
+
+    /// Stores the rightmost bytes of `register` into the field F of location M,
+    /// along with its sign if the field includes byte 0
+    pub(super) fn store(&mut self, register: Word, m: i64, f: u8) -> Result<()> {
+        let field = FieldSpec::from_byte(f)?;
+        let location = self.location(m)?;
+        self.memory[location].set_field(field, &register);
+        Ok(())
+    }
+
+    /// MOVE copies F words, one at a time, from location M to the location in rI1,
+    /// and advances rI1 past them
+    pub(super) fn move_words(&mut self, m: i64, f: u8) -> Result<()> {
+        if f == 0 {
+            return Ok(());
+        }
+        let count = f as i64;
+        let target = self.i[0].value(self.byte_size());
+        self.location(m + count - 1)?;
+        self.location(target + count - 1)?;
+        let from = self.location(m)?;
+        let to = self.location(target)?;
+        let end = Word::from_index(target + count, self.byte_size())?;
+        for k in 0..f as usize {
+            self.memory[to + k] = self.memory[from + k];
+        }
+        self.i[0] = end;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word::Sign;
+
+    #[test]
+    fn test_load_examples() {
+        // TAOCP Vol. I, p. 129, with location 2000 holding - 80 3 5 4
+        let examples = [
+            ("LDA 2000", Word::new(Sign::Minus, [1, 16, 3, 5, 4])),
+            ("LDA 2000(1:5)", Word::new(Sign::Plus, [1, 16, 3, 5, 4])),
+            ("LDA 2000(3:5)", Word::new(Sign::Plus, [0, 0, 3, 5, 4])),
+            ("LDA 2000(0:3)", Word::new(Sign::Minus, [0, 0, 1, 16, 3])),
+            ("LDA 2000(4:4)", Word::new(Sign::Plus, [0, 0, 0, 0, 5])),
+            ("LDA 2000(0:0)", Word::new(Sign::Minus, [0, 0, 0, 0, 0])),
+            ("LDA 2000(1:1)", Word::new(Sign::Plus, [0, 0, 0, 0, 1])),
+        ];
+        for (statement, expected) in examples {
+            let mut machine = Machine::default();
+            machine.memory[2000] = Word::new(Sign::Minus, [1, 16, 3, 5, 4]);
+            machine.a = Word::new(Sign::Plus, [9, 9, 9, 9, 9]);
+            machine.execute_statement(statement).unwrap();
+            assert_eq!(machine.a, expected, "{}", statement);
+
+            let statement = statement.replace("LDA", "LDX");
+            machine.execute_statement(&statement).unwrap();
+            assert_eq!(machine.x, expected, "{}", statement);
+
+            let statement = statement.replace("LDX", "LDAN");
+            machine.execute_statement(&statement).unwrap();
+            let negated = Word::new(expected.sign.flip(), expected.bytes);
+            assert_eq!(machine.a, negated, "{}", statement);
+        }
+    }
+
+    #[test]
+    fn test_store_examples() {
+        // TAOCP Vol. I, p. 130, with rA holding + 6 7 8 9 0 and location 2000
+        // holding - 1 2 3 4 5
+        let examples = [
+            ("STA 2000", Word::new(Sign::Plus, [6, 7, 8, 9, 0])),
+            ("STA 2000(1:5)", Word::new(Sign::Minus, [6, 7, 8, 9, 0])),
+            ("STA 2000(5:5)", Word::new(Sign::Minus, [1, 2, 3, 4, 0])),
+            ("STA 2000(2:2)", Word::new(Sign::Minus, [1, 0, 3, 4, 5])),
+            ("STA 2000(2:3)", Word::new(Sign::Minus, [1, 9, 0, 4, 5])),
+            ("STA 2000(0:1)", Word::new(Sign::Plus, [0, 2, 3, 4, 5])),
+        ];
+        for (statement, expected) in examples {
+            let mut machine = Machine::default();
+            machine.memory[2000] = Word::new(Sign::Minus, [1, 2, 3, 4, 5]);
+            machine.a = Word::new(Sign::Plus, [6, 7, 8, 9, 0]);
+            machine.x = machine.a;
+            machine.execute_statement(statement).unwrap();
+            assert_eq!(machine.memory[2000], expected, "{}", statement);
+
+            machine.memory[2000] = Word::new(Sign::Minus, [1, 2, 3, 4, 5]);
+            let statement = statement.replace("STA", "STX");
+            machine.execute_statement(&statement).unwrap();
+            assert_eq!(machine.memory[2000], expected, "{}", statement);
+        }
+    }
+
+    #[test]
+    fn test_index_registers() {
+        let mut machine = Machine::default();
+        machine.memory[2000] = Word::new(Sign::Minus, [1, 16, 3, 5, 4]);
+        machine.execute_statement("LD3 2000(4:5)").unwrap();
+        assert_eq!(machine.i[2], Word::new(Sign::Plus, [0, 0, 0, 5, 4]));
+        machine.execute_statement("LD6N 2000(0:0)").unwrap();
+        assert_eq!(machine.i[5], Word::new(Sign::Plus, [0, 0, 0, 0, 0]));
+        machine.execute_statement("LD1 2000(0:2)").unwrap();
+        assert_eq!(machine.i[0], Word::new(Sign::Minus, [0, 0, 0, 1, 16]));
+
+        // Bytes 1-3 must be zero
+        let fault = machine.execute_statement("LD2 2000(0:3)").unwrap_err();
+        assert_eq!(
+            fault.message,
+            "- 00 00 01 16 03 does not fit in index register 2"
+        );
+        assert_eq!(machine.i[1], Word::ZERO);
+
+        machine.execute_statement("ST3 2000").unwrap();
+        assert_eq!(machine.memory[2000], Word::new(Sign::Plus, [0, 0, 0, 5, 4]));
+        machine.execute_statement("ST1 2000(1:1)").unwrap();
+        assert_eq!(
+            machine.memory[2000],
+            Word::new(Sign::Plus, [16, 0, 0, 5, 4])
+        );
+    }
+
+    #[test]
+    fn test_stj_and_stz() {
+        let mut machine = Machine::default();
+        machine.memory[2000] = Word::new(Sign::Minus, [1, 2, 3, 4, 5]);
+        machine.j = Word::from_index(3009, 64).unwrap();
+        // STJ stores into the address part by default
+        machine.execute_statement("STJ 2000").unwrap();
+        assert_eq!(
+            machine.memory[2000],
+            Word::new(Sign::Plus, [47, 1, 3, 4, 5])
+        );
+        machine.execute_statement("STZ 2000(3:4)").unwrap();
+        assert_eq!(
+            machine.memory[2000],
+            Word::new(Sign::Plus, [47, 1, 0, 0, 5])
+        );
+        machine.execute_statement("STZ 2000").unwrap();
+        assert_eq!(machine.memory[2000], Word::ZERO);
+    }
+
+    #[test]
+    fn test_move() {
+        let mut machine = Machine::default();
+        for k in 0..5 {
+            machine.memory[1000 + k] = Word::from_value(k as i64 + 1, 64).unwrap();
+        }
+        machine.i[0] = Word::from_index(2000, 64).unwrap();
+        machine.execute_statement("MOVE 1000(3)").unwrap();
+        let moved: Vec<_> = machine.memory[2000..2004]
+            .iter()
+            .map(|w| w.value(64))
+            .collect();
+        assert_eq!(moved, [1, 2, 3, 0]);
+        assert_eq!(machine.i[0].value(64), 2003);
+
+        // Words are moved one at a time, so an overlapping move repeats the first
+        machine.i[0] = Word::from_index(1001, 64).unwrap();
+        machine.execute_statement("MOVE 1000(4)").unwrap();
+        let moved: Vec<_> = machine.memory[1000..1005]
+            .iter()
+            .map(|w| w.value(64))
+            .collect();
+        assert_eq!(moved, [1, 1, 1, 1, 1]);
+
+        machine.execute_statement("MOVE 1000(0)").unwrap();
+        assert_eq!(machine.i[0].value(64), 1005);
+        machine.i[0] = Word::from_index(3998, 64).unwrap();
+        assert!(machine.execute_statement("MOVE 1000(3)").is_err());
+        assert_eq!(machine.memory[3998], Word::ZERO);
+    }
+
+    #[test]
+    fn test_faults() {
+        let mut machine = Machine::default();
+        assert!(machine.execute_statement("LDA 4000").is_err());
+        assert!(machine.execute_statement("STA -1").is_err());
+        assert!(machine.execute_statement("LDA 2000(5:3)").is_err());
+        assert!(machine.execute_statement("STZ 2000(7)").is_err());
+    }
+}
@@ -0,0 +1,408 @@
+use std::fmt;
+
+use anyhow::{Result, bail};
+
+use crate::mixal::assemble::{BYTE_SIZE, N_WORDS};
+use crate::mixal::image::Image;
+use crate::word::{BYTES, FieldSpec, Sign, Word};
+
+/// The comparison indicator, set by the CMP instructions
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    #[default]
+    Equal,
+    Greater,
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Comparison::Less => "LESS",
+            Comparison::Equal => "EQUAL",
+            Comparison::Greater => "GREATER",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// An instruction word taken apart into its ±AA, I, F and C parts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instruction {
+    /// The signed address part, bytes 1 and 2
+    pub address: i64,
+    /// The sign of the instruction, which tells -0 from +0 in the address part
+    pub sign: Sign,
+    pub index: u8,
+    pub field: u8,
+    pub opcode: u8,
+}
+
+impl Instruction {
+    pub fn decode(word: &Word, byte_size: i64) -> Self {
+        let [a1, a2, index, field, opcode] = word.bytes;
+        let magnitude = a1 as i64 * byte_size + a2 as i64;
+        Instruction {
+            address: match word.sign {
+                Sign::Plus => magnitude,
+                Sign::Minus => -magnitude,
+            },
+            sign: word.sign,
+            index,
+            field,
+            opcode,
+        }
+    }
+}
+
+/// Returned when the machine can't go on executing the program, e.g. on an invalid
+/// instruction or an address outside of memory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fault {
+    /// The location of the instruction at fault
+    pub location: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Fault at {:04}: {}", self.location, self.message)
+    }
+}
+
+impl std::error::Error for Fault {}
+
+/// The state of a MIX computer: its memory, registers, overflow toggle and
+/// comparison indicator. The index registers and rJ are kept as words whose bytes
+/// 1-3 are zero.
+#[derive(Debug, Clone)]
+pub struct Machine {
+    pub memory: Vec<Word>,
+    pub a: Word,
+    pub x: Word,
+    /// rI1 to rI6
+    pub i: [Word; 6],
+    pub j: Word,
+    pub overflow: bool,
+    pub comparison: Comparison,
+    /// The location of the next instruction
+    pub pc: usize,
+    pub halted: bool,
+    /// The number of instructions executed so far
+    pub steps: u64,
+    byte_size: i64,
+}
+
+impl Machine {
+    /// A machine with every word and register +0, whose bytes hold `byte_size`
+    /// values, which must be between 64 and 100
+    pub fn new(byte_size: i64) -> Result<Self> {
+        if !(64..=100).contains(&byte_size) {
+            bail!("A byte must hold from 64 to 100 values, not {}", byte_size);
+        }
+        Ok(Machine {
+            memory: vec![Word::ZERO; N_WORDS],
+            a: Word::ZERO,
+            x: Word::ZERO,
+            i: [Word::ZERO; 6],
+            j: Word::ZERO,
+            overflow: false,
+            comparison: Comparison::default(),
+            pc: 0,
+            halted: false,
+            steps: 0,
+            byte_size,
+        })
+    }
+
+    pub fn byte_size(&self) -> i64 {
+        self.byte_size
+    }
+
+    /// Loads the words of an image into memory and gets ready to run from its
+    /// start address
+    pub fn load(&mut self, image: &Image) -> Result<()> {
+        let Some(start) = image.start else {
+            bail!("The image has no start address; give one with END");
+        };
+        for (&address, word) in &image.words {
+            if word.bytes.iter().any(|&b| b as i64 >= self.byte_size) {
+                bail!(
+                    "Word {} at {:04} has a byte too large for {}-valued bytes",
+                    word,
+                    address,
+                    self.byte_size
+                );
+            }
+            self.memory[address] = *word;
+        }
+        self.pc = start;
+        self.halted = false;
+        Ok(())
+    }
+
+    /// Executes the next instruction. On a fault, `pc` is left pointing at the
+    /// instruction; instructions check everything that can fail before changing
+    /// any register or memory, so the machine is as it was before.
+    pub fn step(&mut self) -> Result<(), Fault> {
+        let (location, steps) = (self.pc, self.steps);
+        self.execute_next().map_err(|err| {
+            self.pc = location;
+            self.steps = steps;
+            Fault {
+                location,
+                message: format!("{:#}", err),
+            }
+        })
+    }
+
+    /// Executes instructions until HLT
+    pub fn run(&mut self) -> Result<(), Fault> {
+        while !self.halted {
+            self.step()?;
+        }
+        Ok(())
+    }
+
+    fn execute_next(&mut self) -> Result<()> {
+        let Some(&word) = self.memory.get(self.pc) else {
+            bail!("Ran past the end of memory");
+        };
+        let instruction = Instruction::decode(&word, self.byte_size);
+        let m = self.effective_address(&instruction)?;
+        self.pc += 1;
+        self.steps += 1;
+        self.execute(instruction, m)
+    }
+
+    /// M, the address part plus the contents of the index register
+    fn effective_address(&self, instruction: &Instruction) -> Result<i64> {
+        let m = match instruction.index {
+            0 => instruction.address,
+            i @ 1..=6 => instruction.address + self.i[i as usize - 1].value(self.byte_size),
+            i => bail!("Invalid index register {}", i),
+        };
+        if m.abs() >= self.byte_size * self.byte_size {
+            bail!("Effective address {} does not fit in two bytes", m);
+        }
+        Ok(m)
+    }
+
+    /// Checks that M is a memory location
+    pub(super) fn location(&self, m: i64) -> Result<usize> {
+        if !(0..N_WORDS as i64).contains(&m) {
+            bail!("Address {} is outside of memory", m);
+        }
+        Ok(m as usize)
+    }
+
+    /// The field F of location M, as loaded by LDA
+    pub(super) fn operand(&self, m: i64, f: u8) -> Result<Word> {
+        Ok(self.memory[self.location(m)?].field(FieldSpec::from_byte(f)?))
+    }
+
+    /// A register numbered the way the operation codes order them: 0 for rA, 1-6
+    /// for rI1-rI6 and 7 for rX
+    pub(super) fn register(&mut self, r: u8) -> &mut Word {
+        match r {
+            0 => &mut self.a,
+            1..=6 => &mut self.i[r as usize - 1],
+            7 => &mut self.x,
+            _ => unreachable!("There is no register {}", r),
+        }
+    }
+
+    fn execute(&mut self, instruction: Instruction, m: i64) -> Result<()> {
+        let field = instruction.field;
+        match (instruction.opcode, field) {
+            // NOP
+            (0, _) => {}
+            // FADD, FSUB, FMUL, FDIV
+            #[cfg(feature = "float")]
+            (c @ 1..=4, 6) => self.float_arithmetic(c, m)?,
+            // ADD, SUB
+            (c @ 1..=2, _) => self.add(m, field, c == 2)?,
+            (3, _) => self.multiply(m, field)?,
+            (4, _) => self.divide(m, field)?,
+            // SLB, SRB
+            (6, 6..=7) => self.binary_shift(m, field)?,
+            (6, _) => self.shift(m, field)?,
+            (7, _) => self.move_words(m, field)?,
+            (5, 0) => self.num(),
+            (5, 1) => self.char(),
+            // HLT
+            (5, 2) => self.halted = true,
+            #[cfg(feature = "float")]
+            (5, 6) => self.flot(),
+            #[cfg(feature = "float")]
+            (5, 7) => self.fix(),
+            // LDA, LD1-LD6, LDX and their negating forms
+            (c @ 8..=23, _) => self.load_register((c - 8) % 8, c >= 16, m, field)?,
+            // STA, ST1-ST6, STX
+            (c @ 24..=31, _) => {
+                let register = *self.register(c - 24);
+                self.store(register, m, field)?
+            }
+            // STJ
+            (32, _) => self.store(self.j, m, field)?,
+            // STZ
+            (33, _) => self.store(Word::ZERO, m, field)?,
+            (39, _) => self.jump(m, field)?,
+            // JAN, JAZ, ..., JXNP
+            (c @ 40..=47, 0..=5) => self.jump_register(c - 40, m, field)?,
+            // JAE, JAO, JXE, JXO
+            (c @ (40 | 47), 6..=7) => self.jump_parity(c - 40, m, field)?,
+            // INC, DEC, ENT and ENN for rA, rI1-rI6 and rX
+            (c @ 48..=55, 0..=3) => self.transfer(c - 48, field, m, instruction.sign)?,
+            #[cfg(feature = "float")]
+            (56, 6) => self.float_compare(m)?,
+            // CMPA, CMP1-CMP6, CMPX
+            (c @ 56..=63, _) => self.compare(c - 56, m, field)?,
+            (c, f) => bail!("Invalid instruction: C = {}, F = {}", c, f),
+        }
+        Ok(())
+    }
+}
+
+impl Default for Machine {
+    fn default() -> Self {
+        Machine::new(BYTE_SIZE).unwrap()
+    }
+}
+
+/// Formats a register that holds a sign and two bytes
+fn short_register(word: &Word) -> String {
+    let sign = if word.is_negative() { '-' } else { '+' };
+    format!(
+        "{} {:02} {:02}",
+        sign,
+        word.bytes[BYTES - 2],
+        word.bytes[BYTES - 1]
+    )
+}
+
+impl fmt::Display for Machine {
+    /// Shows the registers, overflow toggle and comparison indicator, one per line
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "rA  {}", self.a)?;
+        writeln!(f, "rX  {}", self.x)?;
+        for (n, i) in self.i.iter().enumerate() {
+            writeln!(f, "rI{} {}", n + 1, short_register(i))?;
+        }
+        writeln!(f, "rJ  {}", short_register(&self.j))?;
+        writeln!(f, "OV  {}", if self.overflow { "ON" } else { "OFF" })?;
+        writeln!(f, "CI  {}", self.comparison)
+    }
+}
+
+#[cfg(test)]
+impl Machine {
+    /// Assembles one MIXAL instruction at `pc` and executes it
+    pub(super) fn execute_statement(&mut self, statement: &str) -> Result<(), Fault> {
+        let source = format!(" ORIG {}\n {}\n", self.pc, statement);
+        let program = crate::mixal::assemble::assemble_source(&source, "test").unwrap();
+        self.memory[self.pc] = program.image.words[&self.pc];
+        self.step()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mixal::assemble::assemble_source;
+
+    fn loaded(source: &str) -> Machine {
+        let program = assemble_source(source, "test").unwrap();
+        let mut machine = Machine::default();
+        machine.load(&program.image).unwrap();
+        machine
+    }
+
+    #[test]
+    fn test_decode() {
+        let word = Word::new(Sign::Minus, [31, 16, 2, 3, 8]);
+        assert_eq!(
+            Instruction::decode(&word, 64),
+            Instruction {
+                address: -2000,
+                sign: Sign::Minus,
+                index: 2,
+                field: 3,
+                opcode: 8,
+            }
+        );
+        assert_eq!(Instruction::decode(&word, 100).address, -3116);
+    }
+
+    #[test]
+    fn test_run_until_halt() {
+        let mut machine = loaded(" ORIG 1000\nSTART NOP\n NOP 5,3\n HLT\n NOP\n END START\n");
+        assert_eq!(machine.pc, 1000);
+        machine.run().unwrap();
+        assert!(machine.halted);
+        assert_eq!(machine.pc, 1003);
+        assert_eq!(machine.steps, 3);
+    }
+
+    #[test]
+    fn test_effective_address() {
+        let mut machine = Machine::default();
+        machine.i[2] = Word::from_index(-10, 64).unwrap();
+        let m = |address, index| {
+            let instruction = Instruction {
+                address,
+                sign: Sign::of(address),
+                index,
+                field: 5,
+                opcode: 8,
+            };
+            machine.effective_address(&instruction).ok()
+        };
+        assert_eq!(m(100, 0), Some(100));
+        assert_eq!(m(100, 3), Some(90));
+        assert_eq!(m(5, 3), Some(-5));
+        assert_eq!(m(-4090, 3), None);
+        assert_eq!(m(100, 7), None);
+    }
+
+    #[test]
+    fn test_faults() {
+        let mut machine = loaded(" ORIG 3998\nSTART NOP\n NOP\n END START\n");
+        let fault = machine.run().unwrap_err();
+        assert_eq!(fault.location, 4000);
+        assert_eq!(
+            fault.to_string(),
+            "Fault at 4000: Ran past the end of memory"
+        );
+
+        let mut machine = loaded("START NOP 0,7\n END START\n");
+        assert_eq!(machine.step().unwrap_err().location, 0);
+        assert_eq!(machine.pc, 0);
+        assert_eq!(machine.steps, 0);
+
+        let mut machine = loaded("START CON 3*64+5\n END START\n");
+        assert!(machine.step().is_err());
+    }
+
+    #[test]
+    fn test_load() {
+        let program = assemble_source(" HLT\n", "test").unwrap();
+        assert!(Machine::default().load(&program.image).is_err());
+        assert!(Machine::new(63).is_err());
+        assert!(Machine::new(100).is_ok());
+    }
+
+    #[test]
+    fn test_display() {
+        let mut machine = Machine {
+            a: Word::from_value(-1, 64).unwrap(),
+            j: Word::from_index(3009, 64).unwrap(),
+            overflow: true,
+            ..Machine::default()
+        };
+        machine.i[0] = Word::from_index(65, 64).unwrap();
+        let text = machine.to_string();
+        assert!(text.starts_with("rA  - 00 00 00 00 01\nrX  + 00 00 00 00 00\nrI1 + 01 01\n"));
+        assert!(text.ends_with("rJ  + 47 01\nOV  ON\nCI  EQUAL\n"));
+    }
+}
@@ -0,0 +1,11 @@
+mod arithmetic;
+mod binary;
+mod compare;
+mod conversion;
+#[cfg(feature = "float")]
+mod float;
+mod jump;
+mod load_store;
+pub mod machine;
+mod shift;
+mod transfer;
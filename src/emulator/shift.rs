@@ -0,0 +1,86 @@
+use anyhow::{Result, bail};
+
+use crate::word::BYTES;
+
+use super::machine::Machine;
+
+/// Shifts bytes left or right by `count` places, filling in zeros
+fn shift_bytes(bytes: &mut [u8], count: usize, left: bool) {
+    let count = count.min(bytes.len());
+    let len = bytes.len();
+    if left {
+        bytes.copy_within(count.., 0);
+        bytes[len - count..].fill(0);
+    } else {
+        bytes.copy_within(..len - count, count);
+        bytes[..count].fill(0);
+    }
+}
+
+impl Machine {
+    /// SLA, SRA, SLAX, SRAX, SLC and SRC shift rA, or rA and rX as one ten-byte
+    /// register, by M bytes. The signs of the registers never change.
+    pub(super) fn shift(&mut self, m: i64, f: u8) -> Result<()> {
+        if m < 0 {
+            bail!("Shift count {} is negative", m);
+        }
+        let count = m as usize;
+        let mut ax = [0; 2 * BYTES];
+        ax[..BYTES].copy_from_slice(&self.a.bytes);
+        ax[BYTES..].copy_from_slice(&self.x.bytes);
+        match f {
+            0 | 1 => shift_bytes(&mut self.a.bytes, count, f == 0),
+            2 | 3 => shift_bytes(&mut ax, count, f == 2),
+            4 => ax.rotate_left(count % (2 * BYTES)),
+            5 => ax.rotate_right(count % (2 * BYTES)),
+            _ => bail!("Invalid instruction: C = 6, F = {}", f),
+        }
+        if f >= 2 {
+            self.a.bytes.copy_from_slice(&ax[..BYTES]);
+            self.x.bytes.copy_from_slice(&ax[BYTES..]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::word::{Sign, Word};
+
+    #[test]
+    fn test_shift_examples() {
+        // The sequence of shifts in TAOCP Vol. I, p. 135
+        let mut machine = Machine::default();
+        machine.a = Word::new(Sign::Plus, [1, 2, 3, 4, 5]);
+        machine.x = Word::new(Sign::Minus, [6, 7, 8, 9, 10]);
+        let steps = [
+            ("SRAX 1", [0, 1, 2, 3, 4], [5, 6, 7, 8, 9]),
+            ("SLA 2", [2, 3, 4, 0, 0], [5, 6, 7, 8, 9]),
+            ("SRC 4", [6, 7, 8, 9, 2], [3, 4, 0, 0, 5]),
+            ("SRA 2", [0, 0, 6, 7, 8], [3, 4, 0, 0, 5]),
+            ("SLC 501", [0, 6, 7, 8, 3], [4, 0, 0, 5, 0]),
+        ];
+        for (statement, a, x) in steps {
+            machine.execute_statement(statement).unwrap();
+            assert_eq!(machine.a, Word::new(Sign::Plus, a), "{}", statement);
+            assert_eq!(machine.x, Word::new(Sign::Minus, x), "{}", statement);
+        }
+    }
+
+    #[test]
+    fn test_long_shifts() {
+        let mut machine = Machine::default();
+        machine.a = Word::new(Sign::Minus, [1, 2, 3, 4, 5]);
+        machine.x = Word::new(Sign::Plus, [6, 7, 8, 9, 10]);
+        machine.execute_statement("SLAX 7").unwrap();
+        assert_eq!(machine.a, Word::new(Sign::Minus, [8, 9, 10, 0, 0]));
+        assert_eq!(machine.x, Word::ZERO);
+        machine.execute_statement("SRA 100").unwrap();
+        assert!(machine.a.is_minus_zero());
+
+        machine.i[0] = Word::from_index(-1, 64).unwrap();
+        let fault = machine.execute_statement("SLA 0,1").unwrap_err();
+        assert_eq!(fault.message, "Shift count -1 is negative");
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::{Result, bail};
+
+use crate::word::{BYTES, Sign, Word};
+
+use super::machine::Machine;
+
+impl Machine {
+    /// INC, DEC, ENT and ENN (F = 0 to 3) for register `r`. INC and DEC work like
+    /// ADD and SUB with M as the operand. ENT and ENN load M or -M, taking the
+    /// sign from the instruction when M is zero, so "ENTA -0" loads -0.
+    pub(super) fn transfer(&mut self, r: u8, f: u8, m: i64, sign: Sign) -> Result<()> {
+        let index = (1..=6).contains(&r);
+        let word = match f {
+            0 | 1 => {
+                let value = if f == 0 { m } else { -m };
+                let register = *self.register(r);
+                let (sum, overflow) = self.sum(register, value);
+                if index && !sum.is_index() {
+                    bail!("The result does not fit in index register {}", r);
+                }
+                self.overflow |= overflow;
+                sum
+            }
+            _ => {
+                let sign = if m == 0 { sign } else { Sign::of(m) };
+                let sign = if f == 3 { sign.flip() } else { sign };
+                Word::from_magnitude(sign, m.unsigned_abs(), BYTES, self.byte_size())?
+            }
+        };
+        *self.register(r) = word;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enter() {
+        let mut machine = Machine::default();
+        machine.execute_statement("ENTA 2000").unwrap();
+        assert_eq!(machine.a.value(64), 2000);
+        machine.execute_statement("ENNX 5").unwrap();
+        assert_eq!(machine.x.value(64), -5);
+        machine.execute_statement("ENT3 -4").unwrap();
+        assert_eq!(machine.i[2].value(64), -4);
+        machine.execute_statement("ENT4 10,3").unwrap();
+        assert_eq!(machine.i[3].value(64), 6);
+
+        // When M is zero, the sign comes from the instruction
+        machine.execute_statement("ENTA 0").unwrap();
+        assert_eq!(machine.a, Word::ZERO);
+        machine.execute_statement("ENTA -0").unwrap();
+        assert!(machine.a.is_minus_zero());
+        machine.execute_statement("ENNX 0").unwrap();
+        assert!(machine.x.is_minus_zero());
+        machine.execute_statement("ENN1 -0").unwrap();
+        assert_eq!(machine.i[0], Word::ZERO);
+
+        // "ENTA 0,1" copies rI1, except that -0 becomes +0
+        machine.i[0] = Word::from_magnitude(Sign::Minus, 0, 2, 64).unwrap();
+        machine.execute_statement("ENTA 0,1").unwrap();
+        assert_eq!(machine.a, Word::ZERO);
+    }
+
+    #[test]
+    fn test_increase() {
+        let mut machine = Machine::default();
+        machine.execute_statement("INCA 100").unwrap();
+        machine.execute_statement("DECA 30").unwrap();
+        assert_eq!(machine.a.value(64), 70);
+        machine.execute_statement("DECX 1").unwrap();
+        machine.execute_statement("INCX 1").unwrap();
+        assert!(machine.x.is_minus_zero());
+        machine.execute_statement("DEC2 1").unwrap();
+        assert_eq!(machine.i[1].value(64), -1);
+        assert!(!machine.overflow);
+
+        // rA and rX overflow like ADD
+        machine.a = Word::new(Sign::Minus, [63; 5]);
+        machine.execute_statement("DECA 2").unwrap();
+        assert!(machine.overflow);
+        assert_eq!(machine.a.value(64), -1);
+    }
+
+    #[test]
+    fn test_index_overflow() {
+        let mut machine = Machine::default();
+        machine.i[4] = Word::from_index(4095, 64).unwrap();
+        let fault = machine.execute_statement("INC5 1").unwrap_err();
+        assert_eq!(fault.message, "The result does not fit in index register 5");
+        assert_eq!(machine.i[4].value(64), 4095);
+        machine.execute_statement("DEC5 4095").unwrap();
+        assert_eq!(machine.i[4], Word::ZERO);
+    }
+}
@@ -1 +1,4 @@
+pub mod emulator;
+pub mod mix_charset;
 pub mod mixal;
+pub mod word;
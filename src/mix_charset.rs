@@ -0,0 +1,141 @@
+/// Every MIX character, indexed by its code, as tabulated in TAOCP Vol. I, p. 140.
+/// Most are ASCII, but codes 10, 20 and 21 are the Greek letters Δ, Σ and Π, and
+/// there are no lowercase letters.
+pub const CHARACTERS: [char; 56] = [
+    ' ', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'Δ', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q',
+    'R', 'Σ', 'Π', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', '0', '1', '2', '3', '4', '5', '6', '7',
+    '8', '9', '.', ',', '(', ')', '+', '-', '*', '/', '=', '$', '<', '>', '@', ';', ':', '\'',
+];
+
+/// Codes of the characters that aren't ASCII
+const NON_ASCII: [u8; 3] = [10, 20, 21];
+
+/// Converts between characters and MIX character codes. Since Δ, Σ and Π are not
+/// ASCII, a charset can also stand in ASCII characters for them, in both directions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Charset {
+    /// ASCII replacements for Δ, Σ and Π, in that order
+    fallback: Option<[char; 3]>,
+}
+
+impl Charset {
+    /// Only the characters of the table itself
+    pub const UNICODE: Charset = Charset { fallback: None };
+
+    /// The ASCII replacements GNU MDK uses: '~' for Δ, '[' for Σ and '#' for Π
+    pub const MDK: Charset = Charset {
+        fallback: Some(['~', '[', '#']),
+    };
+
+    /// A charset writing Δ, Σ and Π as the given ASCII characters
+    pub fn with_fallback(fallback: [char; 3]) -> anyhow::Result<Self> {
+        for c in fallback {
+            if !c.is_ascii() || CHARACTERS.contains(&c) {
+                anyhow::bail!("'{}' can't stand in for a MIX character", c);
+            }
+        }
+        Ok(Charset {
+            fallback: Some(fallback),
+        })
+    }
+
+    /// The MIX code for `c`, if it has one
+    pub fn encode(&self, c: char) -> Option<u8> {
+        if let Some(code) = CHARACTERS.iter().position(|&m| m == c) {
+            return Some(code as u8);
+        }
+        let fallback = self.fallback?;
+        let idx = fallback.iter().position(|&f| f == c)?;
+        Some(NON_ASCII[idx])
+    }
+
+    /// The character for a MIX code, or None if the code is 56 or more
+    pub fn decode(&self, code: u8) -> Option<char> {
+        let c = *CHARACTERS.get(code as usize)?;
+        match (self.fallback, NON_ASCII.iter().position(|&n| n == code)) {
+            (Some(fallback), Some(idx)) => Some(fallback[idx]),
+            _ => Some(c),
+        }
+    }
+
+    /// Encodes every character of `s`, failing on the first one MIX doesn't have
+    pub fn encode_str(&self, s: &str) -> anyhow::Result<Vec<u8>> {
+        s.chars()
+            .map(|c| {
+                self.encode(c)
+                    .ok_or_else(|| anyhow::anyhow!("'{}' is not a MIX character", c))
+            })
+            .collect()
+    }
+
+    /// Decodes a sequence of codes, failing on the first one that isn't a character
+    pub fn decode_str(&self, codes: &[u8]) -> anyhow::Result<String> {
+        codes
+            .iter()
+            .map(|&code| {
+                self.decode(code)
+                    .ok_or_else(|| anyhow::anyhow!("{} is not a MIX character code", code))
+            })
+            .collect()
+    }
+}
+
+impl Default for Charset {
+    fn default() -> Self {
+        Charset::UNICODE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table() {
+        let charset = Charset::UNICODE;
+        assert_eq!(charset.encode(' '), Some(0));
+        assert_eq!(charset.encode('A'), Some(1));
+        assert_eq!(charset.encode('Δ'), Some(10));
+        assert_eq!(charset.encode('J'), Some(11));
+        assert_eq!(charset.encode('Σ'), Some(20));
+        assert_eq!(charset.encode('Π'), Some(21));
+        assert_eq!(charset.encode('S'), Some(22));
+        assert_eq!(charset.encode('0'), Some(30));
+        assert_eq!(charset.encode('.'), Some(40));
+        assert_eq!(charset.encode('\''), Some(55));
+        assert_eq!(charset.encode('a'), None);
+        assert_eq!(charset.encode('#'), None);
+        assert_eq!(charset.decode(56), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for charset in [Charset::UNICODE, Charset::MDK] {
+            for code in 0..56 {
+                let c = charset.decode(code).unwrap();
+                assert_eq!(charset.encode(c), Some(code));
+            }
+        }
+    }
+
+    #[test]
+    fn test_fallback() {
+        let charset = Charset::MDK;
+        assert_eq!(charset.encode('~'), Some(10));
+        assert_eq!(charset.encode('Δ'), Some(10));
+        assert_eq!(charset.decode(20), Some('['));
+        assert_eq!(charset.decode_str(&[8, 1, 21]).unwrap(), "HA#");
+        assert_eq!(Charset::UNICODE.decode_str(&[8, 1, 21]).unwrap(), "HAΠ");
+
+        let custom = Charset::with_fallback(['^', '&', '%']).unwrap();
+        assert_eq!(custom.encode_str("^&%").unwrap(), vec![10, 20, 21]);
+        assert!(Charset::with_fallback(['A', '&', '%']).is_err());
+        assert!(Charset::with_fallback(['ß', '&', '%']).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Charset::UNICODE.encode_str("HELLO!").is_err());
+        assert!(Charset::UNICODE.decode_str(&[1, 60]).is_err());
+    }
+}
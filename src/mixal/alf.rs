@@ -1,7 +1,14 @@
+use crate::mix_charset::Charset;
+use crate::word::{Sign, Word};
+
+use super::assemble::Dialect;
+
 /// Pseudo-operation in MIXAL that assembles raw characters (text)
 #[derive(Debug, PartialEq)]
 pub struct Alf {
     pub chars: [char; 5],
+    /// The MIX character code of each character
+    pub codes: [u8; 5],
 }
 
 impl Alf {
@@ -9,37 +16,62 @@ impl Alf {
     /// to assemble.
     /// Should begin with one or two blank spaces, and then exactly 5 characters.
     /// If one blank space -- then the first character must be non blank
-    pub fn from_char_data(s: &str) -> anyhow::Result<Self> {
-        let char_data = if s.starts_with("  ") {
-            &s[2..]
-        } else if s.starts_with(" ") {
-            &s[1..]
+    pub fn from_char_data(s: &str, charset: Charset) -> anyhow::Result<Self> {
+        let char_data = if let Some(rest) = s.strip_prefix("  ") {
+            rest
+        } else if let Some(rest) = s.strip_prefix(" ") {
+            rest
         } else {
             anyhow::bail!(
                 "ALF pseudo-op character data must have exactly one or two leading blank spaces"
             );
         };
 
-        if char_data.len() != 5 {
-            anyhow::bail!("ALF pseudo-op must be given exactly 5 characters")
+        let chars: [char; 5] = char_data
+            .chars()
+            .collect::<Vec<_>>()
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("ALF pseudo-op must be given exactly 5 characters"))?;
+        Self::new(chars, charset)
+    }
+
+    /// Parses the operand of ALF. GNU MDK also accepts up to 5 characters in double
+    /// quotes, padded on the right with blanks.
+    pub fn parse(s: &str, dialect: Dialect) -> anyhow::Result<Self> {
+        let charset = dialect.charset();
+        let quoted = s.trim().strip_prefix('"');
+        let Some(quoted) = quoted.filter(|_| dialect == Dialect::Mdk) else {
+            return Self::from_char_data(s, charset);
+        };
+        let Some(char_data) = quoted.strip_suffix('"') else {
+            anyhow::bail!("Missing closing '\"' in ALF character data");
+        };
+
+        if char_data.chars().count() > 5 {
+            anyhow::bail!("ALF pseudo-op can be given at most 5 characters")
         }
 
-        if !char_data.chars().all(|c| Alf::is_valid_mix_character(c)) {
-            anyhow::bail!("Invalid character in {}", char_data)
+        let mut chars = [' '; 5];
+        for (slot, c) in chars.iter_mut().zip(char_data.chars()) {
+            *slot = c;
         }
+        Self::new(chars, charset)
+    }
 
-        Ok(Self {
-            chars: char_data
-                .chars()
-                .collect::<Vec<_>>()
-                .try_into()
-                .map_err(|_| anyhow::anyhow!("Expected exactly 5 characters"))?,
-        })
+    /// The word ALF assembles to: a + sign and the five character codes
+    pub fn word(&self) -> Word {
+        Word::new(Sign::Plus, self.codes)
     }
 
-    /// Checks if c is one of the 60 valid characters listed in the reference at the back of the book
-    fn is_valid_mix_character(c: char) -> bool {
-        c.is_ascii_uppercase() || c.is_ascii_digit() || " .,()+-*/=$<>@;:'".contains(c)
+    /// Looks up the code of each character, which must be in the MIX character set
+    pub fn new(chars: [char; 5], charset: Charset) -> anyhow::Result<Self> {
+        let mut codes = [0; 5];
+        for (code, &c) in codes.iter_mut().zip(&chars) {
+            *code = charset.encode(c).ok_or_else(|| {
+                anyhow::anyhow!("Invalid character in {}", String::from_iter(chars))
+            })?;
+        }
+        Ok(Self { chars, codes })
     }
 }
 
@@ -49,7 +81,7 @@ mod tests {
 
     #[test]
     fn test_two_leading_spaces() {
-        let result = Alf::from_char_data("  HELLO");
+        let result = Alf::from_char_data("  HELLO", Charset::UNICODE);
         assert!(result.is_ok());
         let alf = result.unwrap();
         assert_eq!(alf.chars, ['H', 'E', 'L', 'L', 'O']);
@@ -57,7 +89,7 @@ mod tests {
 
     #[test]
     fn test_two_leading_spaces_with_space_in_data() {
-        let result = Alf::from_char_data("  HE LO");
+        let result = Alf::from_char_data("  HE LO", Charset::UNICODE);
         assert!(result.is_ok());
         let alf = result.unwrap();
         assert_eq!(alf.chars, ['H', 'E', ' ', 'L', 'O']);
@@ -65,7 +97,7 @@ mod tests {
 
     #[test]
     fn test_one_leading_space_non_blank_first() {
-        let result = Alf::from_char_data(" WORLD");
+        let result = Alf::from_char_data(" WORLD", Charset::UNICODE);
         assert!(result.is_ok());
         let alf = result.unwrap();
         assert_eq!(alf.chars, ['W', 'O', 'R', 'L', 'D']);
@@ -73,7 +105,7 @@ mod tests {
 
     #[test]
     fn test_digits_and_punctuation() {
-        let result = Alf::from_char_data("  123+5");
+        let result = Alf::from_char_data("  123+5", Charset::UNICODE);
         assert!(result.is_ok());
         let alf = result.unwrap();
         assert_eq!(alf.chars, ['1', '2', '3', '+', '5']);
@@ -81,15 +113,25 @@ mod tests {
 
     #[test]
     fn test_all_punctuation() {
-        let result = Alf::from_char_data("  .,();");
+        let result = Alf::from_char_data("  .,();", Charset::UNICODE);
         assert!(result.is_ok());
         let alf = result.unwrap();
         assert_eq!(alf.chars, ['.', ',', '(', ')', ';']);
     }
 
+    #[test]
+    fn test_codes() {
+        let alf = Alf::from_char_data(" ΔΣΠ.'", Charset::UNICODE).unwrap();
+        assert_eq!(alf.chars, ['Δ', 'Σ', 'Π', '.', '\''],);
+        assert_eq!(alf.codes, [10, 20, 21, 40, 55]);
+        let alf = Alf::from_char_data("  A9 J0", Charset::UNICODE).unwrap();
+        assert_eq!(alf.codes, [1, 39, 0, 11, 30]);
+        assert_eq!(alf.word().to_bytes(), [0, 1, 39, 0, 11, 30]);
+    }
+
     #[test]
     fn test_no_leading_spaces() {
-        let result = Alf::from_char_data("HELLO");
+        let result = Alf::from_char_data("HELLO", Charset::UNICODE);
         assert!(result.is_err());
         assert!(
             result
@@ -102,7 +144,7 @@ mod tests {
     #[test]
     fn test_three_spaces_valid() {
         // Two leading spaces + space as first char + 4 more chars
-        let result = Alf::from_char_data("   HELL");
+        let result = Alf::from_char_data("   HELL", Charset::UNICODE);
         assert!(result.is_ok());
         let alf = result.unwrap();
         assert_eq!(alf.chars, [' ', 'H', 'E', 'L', 'L']);
@@ -110,7 +152,7 @@ mod tests {
 
     #[test]
     fn test_too_few_characters() {
-        let result = Alf::from_char_data("  HELL");
+        let result = Alf::from_char_data("  HELL", Charset::UNICODE);
         assert!(result.is_err());
         assert!(
             result
@@ -122,7 +164,7 @@ mod tests {
 
     #[test]
     fn test_too_many_characters() {
-        let result = Alf::from_char_data("  HELLOO");
+        let result = Alf::from_char_data("  HELLOO", Charset::UNICODE);
         assert!(result.is_err());
         assert!(
             result
@@ -134,7 +176,7 @@ mod tests {
 
     #[test]
     fn test_invalid_character_lowercase() {
-        let result = Alf::from_char_data("  HeLLO");
+        let result = Alf::from_char_data("  HeLLO", Charset::UNICODE);
         assert!(result.is_err());
         assert!(
             result
@@ -146,7 +188,7 @@ mod tests {
 
     #[test]
     fn test_invalid_character_special() {
-        let result = Alf::from_char_data("  HEL#O");
+        let result = Alf::from_char_data("  HEL#O", Charset::UNICODE);
         assert!(result.is_err());
         assert!(
             result
@@ -158,7 +200,7 @@ mod tests {
 
     #[test]
     fn test_empty_after_spaces() {
-        let result = Alf::from_char_data("  ");
+        let result = Alf::from_char_data("  ", Charset::UNICODE);
         assert!(result.is_err());
         assert!(
             result
@@ -167,4 +209,24 @@ mod tests {
                 .contains("exactly 5 characters")
         );
     }
+
+    #[test]
+    fn test_mdk_quoted() {
+        let alf = Alf::parse(" \"HI\"", Dialect::Mdk).unwrap();
+        assert_eq!(alf.chars, ['H', 'I', ' ', ' ', ' ']);
+        let alf = Alf::parse("  \"HE LO\"", Dialect::Mdk).unwrap();
+        assert_eq!(alf.chars, ['H', 'E', ' ', 'L', 'O']);
+        // Unquoted character data still works
+        let alf = Alf::parse("  HELLO", Dialect::Mdk).unwrap();
+        assert_eq!(alf.chars, ['H', 'E', 'L', 'L', 'O']);
+
+        assert!(Alf::parse(" \"HELLOS\"", Dialect::Mdk).is_err());
+        assert!(Alf::parse(" \"HELLO", Dialect::Mdk).is_err());
+        assert!(Alf::parse(" \"a\"", Dialect::Mdk).is_err());
+        // MDK writes Δ, Σ and Π as '~', '[' and '#'
+        let alf = Alf::parse(" \"A#B~\"", Dialect::Mdk).unwrap();
+        assert_eq!(alf.codes, [1, 21, 2, 10, 0]);
+        // Quotes are just characters to historical MIXAL, and not valid ones
+        assert!(Alf::parse(" \"HI\"", Dialect::Knuth).is_err());
+    }
 }
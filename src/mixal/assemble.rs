@@ -1,34 +1,1617 @@
 use std::{
-    collections::HashMap,
-    fs::File,
-    io::{BufRead, BufReader},
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    io::{self, BufRead},
+    rc::Rc,
+    str::FromStr,
 };
 
-use super::expression::Expression;
-use super::statement::Statement;
-use anyhow::Result;
+use anyhow::{Context, Result, anyhow, bail};
 
-// A MIX machine consists of 4000 machine words which are each represented as 6 u8 bytes.
-const N_WORDS: usize = 4000;
-const BYTES_PER_WORD: usize = 6;
+use crate::mix_charset::Charset;
+use crate::word::{Sign, Word};
 
-pub struct MachineWord {
-    bytes: [u8; BYTES_PER_WORD],
-    symbols: HashMap<String, Expression>,
+use super::alf::Alf;
+use super::debug_info::{DebugInfo, SourceLine, SymbolDefinition, SymbolKind};
+use super::diagnostic::{Code, Diagnostic, Note, Severity, Sources, Span, Stage};
+use super::expression::Environment;
+use super::image::Image;
+use super::instruction::{self, MixInstruction};
+use super::listing::{Listing, ListingLine, WordLayout};
+use super::reader::{self, Expansion, Line};
+use super::statement::{self, Columns, Operation, Statement};
+use super::symbol::Symbol;
+use super::wval::WVal;
+
+// A MIX machine consists of 4000 words
+pub const N_WORDS: usize = 4000;
+
+/// The number of distinct values a single MIX byte can hold. Knuth only guarantees
+/// at least 64, which is what we assemble for.
+pub const BYTE_SIZE: i64 = 64;
+
+/// One more than the largest magnitude a MIX word can hold
+pub const WORD_SIZE: i64 = BYTE_SIZE * BYTE_SIZE * BYTE_SIZE * BYTE_SIZE * BYTE_SIZE;
+
+/// The flavor of MIXAL to accept
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Dialect {
+    /// MIXAL exactly as described in TAOCP Vol. I, section 1.3.2
+    #[default]
+    Knuth,
+    /// The MIXAL accepted by GNU MDK's mixasm: mnemonics in any case, ALF with a
+    /// quoted string, symbols longer than 10 characters, `#` comments and remarks
+    /// after the address
+    Mdk,
+}
+
+impl Dialect {
+    /// The characters ALF accepts. GNU MDK also writes Δ, Σ and Π as '~', '[' and '#';
+    /// a '#' in ALF data, quoted or not, is Π rather than the start of a comment.
+    pub fn charset(self) -> Charset {
+        match self {
+            Dialect::Knuth => Charset::UNICODE,
+            Dialect::Mdk => Charset::MDK,
+        }
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "knuth" => Ok(Dialect::Knuth),
+            "mdk" => Ok(Dialect::Mdk),
+            _ => bail!("Unknown dialect '{}', expected knuth or mdk", s),
+        }
+    }
+}
+
+/// Settings that change how a program is assembled
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Treat every warning as an error
+    pub strict: bool,
+    /// Which flavor of MIXAL to accept
+    pub dialect: Dialect,
+    /// Allow macros to be defined with MACRO and ENDM
+    pub macros: bool,
+    /// Symbols defined before the first line of the program
+    pub predefined: Vec<Definition>,
+}
+
+/// A symbol defined from outside the program, e.g. with `-D BUFSIZE=100`. It is
+/// treated as an EQU before the first line, so the value can refer to earlier definitions.
+/// Both parts are kept as written, as what makes a valid symbol depends on the dialect.
+#[derive(Debug, Clone)]
+pub struct Definition {
+    name: String,
+    value: String,
+}
+
+impl Definition {
+    /// Parses the name and value in the given dialect
+    fn parse(&self, dialect: Dialect) -> Result<(Symbol, WVal)> {
+        Ok((
+            Symbol::parse(&self.name, dialect)?,
+            WVal::parse(&self.value, dialect)?,
+        ))
+    }
+}
+
+impl FromStr for Definition {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((name, value)) = s
+            .split_once('=')
+            .filter(|(n, v)| !n.is_empty() && !v.is_empty())
+        else {
+            bail!("Expected NAME=value, found '{}'", s);
+        };
+        Ok(Definition {
+            name: name.to_string(),
+            value: value.to_string(),
+        })
+    }
+}
+
+/// The result of assembling a MIXAL program. If any of the diagnostics are errors,
+/// the image is incomplete.
+#[derive(Debug)]
+pub struct Program {
+    pub image: Image,
+    /// The value of every symbol the program defined
+    pub symbols: BTreeMap<String, i64>,
+    pub listing: Listing,
+    pub debug_info: DebugInfo,
+    pub diagnostics: Vec<Diagnostic>,
+    /// The source the program was assembled from, for rendering diagnostics
+    pub sources: Sources,
+}
+
+impl Program {
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+}
+
+/// Something that has to be placed in memory once the END of the program is reached
+enum Deferred {
+    /// A literal constant "=W=" used by the statement at the given index
+    Literal(usize),
+    /// A symbol referenced by the statement at the given index, which may never be defined
+    Symbol(String, usize),
+}
+
+/// A statement together with where it came from
+struct SourceStatement {
+    /// Index of the statement's line among all the lines read
+    index: usize,
+    file: String,
+    line: usize,
+    text: String,
+    columns: Columns,
+    /// The macro call the statement was expanded from, if any
+    expansion: Option<Rc<Expansion>>,
+    statement: Statement,
+}
+
+/// An IF whose ENDIF hasn't been reached yet
+struct Condition {
+    /// Index of the IF statement
+    statement: usize,
+    /// Whether the IF's expression was nonzero
+    taken: bool,
+    /// Whether its ELSE has been reached
+    in_else: bool,
+}
+
+impl Condition {
+    /// Whether the statements currently being read are assembled
+    fn active(&self) -> bool {
+        self.taken != self.in_else
+    }
+}
+
+/// Returned when a symbol is defined a second time
+#[derive(Debug)]
+struct DuplicateSymbol {
+    name: String,
+}
+
+impl fmt::Display for DuplicateSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Symbol '{}' is defined more than once", self.name)
+    }
+}
+
+impl std::error::Error for DuplicateSymbol {}
+
+/// Returned when the location counter leaves memory
+#[derive(Debug)]
+struct OutOfMemory {
+    location: usize,
+}
+
+impl fmt::Display for OutOfMemory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Location {} is outside of memory, which ends at {}",
+            self.location,
+            N_WORDS - 1
+        )
+    }
+}
+
+impl std::error::Error for OutOfMemory {}
+
+/// How far the first pass has got through the statements read so far
+#[derive(Default)]
+struct FirstPass {
+    location: usize,
+    deferred: Vec<Deferred>,
+    conditions: Vec<Condition>,
+    /// Whether END has been reached
+    ended: bool,
+    /// Whether a statement after END has been reported
+    ignoring: bool,
+}
+
+impl FirstPass {
+    /// Whether the statements read next are assembled
+    fn active(&self) -> bool {
+        !self.ended && self.conditions.iter().all(Condition::active)
+    }
+}
+
+/// Moves the location counter past the `count` words of a statement, which must all
+/// fit in memory, and returns the address of the first
+fn advance(location: &mut usize, count: usize) -> Result<Option<usize>> {
+    let first = *location;
+    if first + count > N_WORDS {
+        let location = first.max(N_WORDS);
+        return Err(OutOfMemory { location }.into());
+    }
+    *location += count;
+    Ok(Some(first))
+}
+
+struct AssemblerState<'a> {
+    file: &'a str,
+    options: &'a Options,
+    symbols: HashMap<String, i64>,
+    /// Address assigned to each statement that generates a word
+    addresses: Vec<Option<usize>>,
+    literals: HashMap<usize, usize>,
+    /// Number of words reserved by each BLOCK statement
+    blocks: HashMap<usize, usize>,
+    /// Addresses of the literal constants and undefined symbols placed after END
+    placed: Vec<usize>,
+    start: Option<usize>,
+    words: BTreeMap<usize, Word>,
+    debug_info: DebugInfo,
+    diagnostics: Vec<Diagnostic>,
+    /// Where the line being assembled falls among all the lines read, so
+    /// diagnostics can be reported in source order
+    position: usize,
+    /// The position each diagnostic was recorded at
+    positions: Vec<usize>,
+    /// The position of the last statement found overwriting a word, so a statement
+    /// assembling many words is only reported once
+    overlap: Option<usize>,
+}
+
+impl<'a> AssemblerState<'a> {
+    fn new(file: &'a str, options: &'a Options) -> Self {
+        Self {
+            file,
+            options,
+            symbols: HashMap::new(),
+            addresses: Vec::new(),
+            literals: HashMap::new(),
+            blocks: HashMap::new(),
+            placed: Vec::new(),
+            start: None,
+            words: BTreeMap::new(),
+            debug_info: DebugInfo::default(),
+            diagnostics: Vec::new(),
+            position: 0,
+            positions: Vec::new(),
+            overlap: None,
+        }
+    }
+
+    fn diagnose(
+        &mut self,
+        severity: Severity,
+        stage: Stage,
+        code: Code,
+        (file, line, span): (&str, usize, Option<Span>),
+        message: String,
+    ) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            stage,
+            code,
+            file: file.to_string(),
+            line,
+            span,
+            message,
+            notes: Vec::new(),
+        });
+        self.positions.push(self.position);
+    }
+
+    /// Points the last diagnostic at the macro calls its line was expanded from
+    fn note_expansion(&mut self, mut expansion: Option<&Expansion>) {
+        let diagnostic = self.diagnostics.last_mut().unwrap();
+        while let Some(call) = expansion {
+            diagnostic.notes.push(Note {
+                message: format!("in this expansion of macro {}", call.name),
+                file: call.file.clone(),
+                line: call.line,
+                span: call.span,
+            });
+            expansion = call.parent.as_deref();
+        }
+    }
+
+    /// Records a warning, which is an error in strict mode
+    fn warn(&mut self, code: Code, at: (&str, usize, Option<Span>), message: String) {
+        let severity = if self.options.strict {
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+        self.diagnose(severity, Stage::Assemble, code, at, message);
+    }
+
+    /// Records the error from a failed step of assembling a statement, if there was one
+    fn check<T>(&mut self, source: &SourceStatement, result: Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                let columns = &source.columns;
+                let (code, span) = if err.is::<DuplicateSymbol>() {
+                    (Code::DuplicateSymbol, columns.loc)
+                } else if err.is::<OutOfMemory>() {
+                    (Code::OutOfMemory, columns.op)
+                } else {
+                    (Code::InvalidOperand, columns.address.or(columns.op))
+                };
+                let at = (source.file.as_str(), source.line, span);
+                self.diagnose(
+                    Severity::Error,
+                    Stage::Assemble,
+                    code,
+                    at,
+                    format!("{:#}", err),
+                );
+                if let Some(duplicate) = err.downcast_ref::<DuplicateSymbol>() {
+                    let note = self.first_definition(&duplicate.name);
+                    self.diagnostics.last_mut().unwrap().notes.extend(note);
+                }
+                self.note_expansion(source.expansion.as_deref());
+                None
+            }
+        }
+    }
+
+    fn define(
+        &mut self,
+        name: &str,
+        value: i64,
+        kind: SymbolKind,
+        (file, line): (&str, usize),
+    ) -> Result<()> {
+        if self.symbols.insert(name.to_string(), value).is_some() {
+            return Err(DuplicateSymbol {
+                name: name.to_string(),
+            }
+            .into());
+        }
+        self.debug_info.symbols.push(SymbolDefinition {
+            name: name.to_string(),
+            value,
+            kind,
+            file: file.to_string(),
+            line,
+        });
+        Ok(())
+    }
+
+    /// Points at the statement that first defined a symbol
+    fn first_definition(&self, name: &str) -> Option<Note> {
+        let definition = self.debug_info.symbol(name)?;
+        let (message, span) = if definition.line == 0 {
+            ("symbol first defined before the program", None)
+        } else {
+            // Labels always start in the first column
+            let span = Span {
+                start: 1,
+                end: name.chars().count() + 1,
+            };
+            ("symbol first defined here", Some(span))
+        };
+        Some(Note {
+            message: message.to_string(),
+            file: definition.file.clone(),
+            line: definition.line,
+            span,
+        })
+    }
+
+    fn environment(&self, location: usize) -> Environment<'_> {
+        Environment {
+            symbols: &self.symbols,
+            location: location as i64,
+        }
+    }
+
+    fn emit(
+        &mut self,
+        address: usize,
+        word: Word,
+        (file, line): (&str, usize),
+        label: Option<&str>,
+    ) {
+        if self.words.contains_key(&address) && self.overlap != Some(self.position) {
+            self.overlap = Some(self.position);
+            let first = &self.debug_info.lines[&address];
+            let note = Note {
+                message: "first assembled here".to_string(),
+                file: first.file.clone(),
+                line: first.line,
+                span: None,
+            };
+            self.warn(
+                Code::Overlap,
+                (file, line, None),
+                format!(
+                    "Address {} is assembled more than once, overwriting the word from line {}",
+                    address, note.line
+                ),
+            );
+            self.diagnostics.last_mut().unwrap().notes.push(note);
+        }
+        self.words.insert(address, word);
+        self.debug_info.lines.insert(
+            address,
+            SourceLine {
+                file: file.to_string(),
+                line,
+                label: label.map(str::to_string),
+            },
+        );
+    }
+
+    /// Parses the line at the given index among all the lines read, returning None
+    /// for comments and lines that don't parse. Errors aren't reported for lines
+    /// skipped by IF, which may call macros whose definitions were skipped too.
+    fn parse_line(&mut self, idx: usize, line: &Line, skipped: bool) -> Option<SourceStatement> {
+        let dialect = self.options.dialect;
+        let text = statement::code(line.statement(), dialect);
+        // Blank lines and lines beginning with '*' are comments
+        if text.trim().is_empty() || text.starts_with('*') {
+            return None;
+        }
+        // Diagnostics point at the line as written, even if it came from a macro
+        let columns = Columns::of(statement::code(&line.text, dialect));
+        match Statement::parse(text, dialect) {
+            Ok(statement) => Some(SourceStatement {
+                index: idx,
+                file: line.file.clone(),
+                line: line.number,
+                text: line.text.clone(),
+                columns,
+                expansion: line.expansion.clone(),
+                statement,
+            }),
+            Err(_) if skipped => None,
+            Err(err) => {
+                let message = format!("{:#}", err.error);
+                let span = err
+                    .span
+                    .and_then(|span| Columns::of(text).remap(span, &columns));
+                let at = (line.file.as_str(), line.number, span);
+                self.position = idx + 1;
+                self.diagnose(Severity::Error, Stage::Parse, Code::Syntax, at, message);
+                self.note_expansion(line.expansion.as_deref());
+                None
+            }
+        }
+    }
+
+    /// First pass: assigns an address to the statement just read and defines the
+    /// symbols in it. It runs as the program is read, so that INCLUDE and macros can
+    /// be skipped inside an IF that isn't taken.
+    fn assign_next_address(&mut self, statements: &[SourceStatement], pass: &mut FirstPass) {
+        let idx = statements.len() - 1;
+        let source = &statements[idx];
+        self.position = source.index + 1;
+
+        if pass.ended {
+            if !pass.ignoring {
+                pass.ignoring = true;
+                self.warn(
+                    Code::AfterEnd,
+                    (&source.file, source.line, source.columns.op),
+                    "Statements after END are ignored".to_string(),
+                );
+                self.note_expansion(source.expansion.as_deref());
+            }
+            self.addresses.push(None);
+            return;
+        }
+
+        // Statements skipped by IF get no address, so they aren't assembled in
+        // the second pass either
+        if self.conditional(idx, source, &mut pass.conditions, pass.location)
+            || !pass.conditions.iter().all(Condition::active)
+        {
+            self.addresses.push(None);
+            return;
+        }
+
+        let at = (source.file.as_str(), source.line);
+        let result = self.assign_address(idx, source, &mut pass.location, &mut pass.deferred);
+        let address = self.check(source, result).flatten();
+        self.addresses.push(address);
+
+        if let (Some(address), Some(loc)) = (address, &source.statement.loc) {
+            let result = self.define(&loc.0, address as i64, SymbolKind::Label, at);
+            self.check(source, result);
+        }
+
+        if let Operation::End(_) = source.statement.op {
+            pass.ended = true;
+            self.unclosed(&pass.conditions, statements);
+            pass.location = self.place_deferred(pass.location, &pass.deferred, statements);
+            if let Some(loc) = &source.statement.loc {
+                let result = self.define(&loc.0, pass.location as i64, SymbolKind::Label, at);
+                self.check(source, result);
+            }
+        }
+    }
+
+    /// Finishes the first pass once all of the program has been read
+    fn finish_addresses(&mut self, statements: &[SourceStatement], pass: FirstPass) {
+        if pass.ended {
+            return;
+        }
+        self.unclosed(&pass.conditions, statements);
+
+        // A program without an END still gets its constants placed after the last word
+        let (file, line) = statements
+            .last()
+            .map_or((self.file, 0), |s| (s.file.as_str(), s.line));
+        self.position = usize::MAX;
+        self.warn(
+            Code::MissingEnd,
+            (file, line, None),
+            "Program has no END statement".to_string(),
+        );
+        self.place_deferred(pass.location, &pass.deferred, statements);
+    }
+
+    /// Keeps track of IF, ELSE and ENDIF statements, returning false for any other
+    fn conditional(
+        &mut self,
+        idx: usize,
+        source: &SourceStatement,
+        conditions: &mut Vec<Condition>,
+        location: usize,
+    ) -> bool {
+        let active = conditions.iter().all(Condition::active);
+        match &source.statement.op {
+            Operation::If(condition) => {
+                // Conditions in skipped blocks aren't evaluated, as they may refer
+                // to symbols that are never defined
+                let taken = active && {
+                    let result = condition.condition.evaluate(&self.environment(location));
+                    self.check(source, result).is_some_and(|value| value != 0)
+                };
+                conditions.push(Condition {
+                    statement: idx,
+                    taken,
+                    in_else: false,
+                });
+            }
+            Operation::Else => match conditions.last_mut() {
+                Some(condition) if !condition.in_else => condition.in_else = true,
+                Some(_) => self.unbalanced(source, "ELSE after the ELSE of the same IF"),
+                None => self.unbalanced(source, "ELSE without a matching IF"),
+            },
+            Operation::EndIf => {
+                if conditions.pop().is_none() {
+                    self.unbalanced(source, "ENDIF without a matching IF");
+                }
+            }
+            _ => return false,
+        }
+        if let Some(loc) = &source.statement.loc {
+            let message = format!("Label '{}' cannot be defined by a conditional", loc.0);
+            let at = (source.file.as_str(), source.line, source.columns.loc);
+            self.diagnose(
+                Severity::Error,
+                Stage::Assemble,
+                Code::Conditional,
+                at,
+                message,
+            );
+            self.note_expansion(source.expansion.as_deref());
+        }
+        true
+    }
+
+    fn unbalanced(&mut self, source: &SourceStatement, message: &str) {
+        let at = (source.file.as_str(), source.line, source.columns.op);
+        self.diagnose(
+            Severity::Error,
+            Stage::Assemble,
+            Code::Conditional,
+            at,
+            message.to_string(),
+        );
+        self.note_expansion(source.expansion.as_deref());
+    }
+
+    /// Reports every IF that is still open at the end of the program
+    fn unclosed(&mut self, conditions: &[Condition], statements: &[SourceStatement]) {
+        let position = self.position;
+        for condition in conditions {
+            let source = &statements[condition.statement];
+            self.position = source.index + 1;
+            self.unbalanced(source, "IF has no matching ENDIF");
+        }
+        self.position = position;
+    }
+
+    /// Assigns an address to a single statement, advancing the location counter.
+    /// Symbols in the LOC field of statements that don't generate a word are defined here.
+    fn assign_address(
+        &mut self,
+        idx: usize,
+        SourceStatement {
+            file,
+            line,
+            statement,
+            ..
+        }: &SourceStatement,
+        location: &mut usize,
+        deferred: &mut Vec<Deferred>,
+    ) -> Result<Option<usize>> {
+        let at = (file.as_str(), *line);
+        let env = self.environment(*location);
+        match &statement.op {
+            Operation::Equ(equ) => {
+                let value = equ.wval.evaluate(&env)?.value(BYTE_SIZE);
+                if let Some(loc) = &statement.loc {
+                    self.define(&loc.0, value, SymbolKind::Equ, at)?;
+                }
+                Ok(None)
+            }
+            Operation::Orig(orig) => {
+                let value = orig.wval.evaluate(&env)?.value(BYTE_SIZE);
+                if let Some(loc) = &statement.loc {
+                    self.define(&loc.0, *location as i64, SymbolKind::Label, at)?;
+                }
+                let value = usize::try_from(value)
+                    .map_err(|_| anyhow!("ORIG cannot set a negative location: {}", value))?;
+                if value >= N_WORDS {
+                    return Err(OutOfMemory { location: value }.into());
+                }
+                *location = value;
+                Ok(None)
+            }
+            Operation::End(end) => {
+                let start = end.wval.evaluate(&env)?.value(BYTE_SIZE);
+                let start = usize::try_from(start)
+                    .map_err(|_| anyhow!("END cannot start at a negative address: {}", start))?;
+                if start >= N_WORDS {
+                    return Err(OutOfMemory { location: start }.into());
+                }
+                self.start = Some(start);
+                Ok(None)
+            }
+            Operation::Instruction(instruction) => {
+                match &instruction.address.address {
+                    WVal::FutureRef(_) => deferred.push(Deferred::Literal(idx)),
+                    WVal::WValInner(inner) => deferred.extend(
+                        inner
+                            .symbols()
+                            .into_iter()
+                            .map(|s| Deferred::Symbol(s.0.clone(), idx)),
+                    ),
+                }
+                advance(location, 1)
+            }
+            Operation::Con(_) | Operation::Alf(_) => advance(location, 1),
+            Operation::Text(text) => advance(location, text.words.len()),
+            Operation::Block(block) => {
+                let count = block.count.evaluate(&env)?;
+                let count = usize::try_from(count)
+                    .ok()
+                    .filter(|count| (1..=N_WORDS).contains(count))
+                    .ok_or_else(|| {
+                        anyhow!("BLOCK must reserve from 1 to {} words: {}", N_WORDS, count)
+                    })?;
+                self.blocks.insert(idx, count);
+                advance(location, count)
+            }
+            Operation::If(_) | Operation::Else | Operation::EndIf => Ok(None),
+        }
+    }
+
+    /// Places literal constants and never-defined symbols after the end of the program,
+    /// in order of appearance, as Knuth describes for the END pseudo-op. Returns the
+    /// location following the last word placed.
+    fn place_deferred(
+        &mut self,
+        mut location: usize,
+        deferred: &[Deferred],
+        statements: &[SourceStatement],
+    ) -> usize {
+        let position = self.position;
+        for item in deferred {
+            let (Deferred::Literal(idx) | Deferred::Symbol(_, idx)) = item;
+            let source = &statements[*idx];
+            if location >= N_WORDS {
+                if let Deferred::Symbol(name, _) = item {
+                    if self.symbols.contains_key(name) {
+                        continue;
+                    }
+                    // Still define the symbol, so its uses aren't reported as well
+                    let at = (source.file.as_str(), source.line);
+                    let _ = self.define(name, location as i64, SymbolKind::Label, at);
+                }
+                self.position = source.index + 1;
+                self.check(source, Err::<(), _>(OutOfMemory { location }.into()));
+                continue;
+            }
+            match item {
+                Deferred::Literal(idx) => {
+                    self.literals.insert(*idx, location);
+                    self.debug_info.lines.insert(
+                        location,
+                        SourceLine {
+                            file: statements[*idx].file.clone(),
+                            line: statements[*idx].line,
+                            label: None,
+                        },
+                    );
+                }
+                Deferred::Symbol(name, _) => {
+                    if self.symbols.contains_key(name) {
+                        continue;
+                    }
+                    let at = (source.file.as_str(), source.line);
+                    self.position = source.index + 1;
+                    // A symbol passed to a macro isn't in the body as written
+                    let span = source
+                        .columns
+                        .address
+                        .map(|address| address.find(&source.text, name).unwrap_or(address));
+                    self.warn(
+                        Code::UndefinedSymbol,
+                        (at.0, at.1, span),
+                        format!(
+                            "Symbol '{}' is never defined, so it is placed at {} as CON 0",
+                            name, location
+                        ),
+                    );
+                    self.note_expansion(source.expansion.as_deref());
+                    // Can't fail, the symbol isn't defined yet
+                    let _ = self.define(name, location as i64, SymbolKind::Label, at);
+                    self.emit(location, Word::ZERO, at, Some(name));
+                }
+            }
+            self.placed.push(location);
+            location += 1;
+        }
+        self.position = position;
+        location
+    }
+
+    /// Second pass: generates the word for every statement
+    fn generate_words(&mut self, statements: &[SourceStatement]) {
+        for (idx, source) in statements.iter().enumerate() {
+            self.position = source.index + 1;
+            let result = self.generate_word(idx, source);
+            self.check(source, result);
+        }
+    }
+
+    fn generate_word(
+        &mut self,
+        idx: usize,
+        SourceStatement {
+            file,
+            line,
+            statement,
+            ..
+        }: &SourceStatement,
+    ) -> Result<()> {
+        let Some(address) = self.addresses.get(idx).copied().flatten() else {
+            return Ok(());
+        };
+        let env = self.environment(address);
+        let mut literal = None;
+        // The words after the first of a TEXT or BLOCK, which don't get the label
+        let mut following = Vec::new();
+        let word = match &statement.op {
+            Operation::Instruction(instruction) => {
+                let address_value = match &instruction.address.address {
+                    WVal::FutureRef(future_ref) => {
+                        // There's no literal if it didn't fit in memory
+                        let Some(&literal_address) = self.literals.get(&idx) else {
+                            return Ok(());
+                        };
+                        literal = Some((literal_address, future_ref.evaluate(&env)?));
+                        literal_address as i64
+                    }
+                    WVal::WValInner(inner) => inner.evaluate(&env)?.value(BYTE_SIZE),
+                };
+                encode_instruction(instruction, address_value, &env)?
+            }
+            Operation::Con(con) => con.wval.evaluate(&env)?,
+            Operation::Alf(alf) => alf.word(),
+            Operation::Text(text) => {
+                following = text.words[1..].iter().map(Alf::word).collect();
+                text.words[0].word()
+            }
+            Operation::Block(block) => {
+                let fill = match &block.fill {
+                    Some(fill) => fill.evaluate(&env)?,
+                    None => Word::ZERO,
+                };
+                following = vec![fill; self.blocks[&idx] - 1];
+                fill
+            }
+            Operation::Equ(_)
+            | Operation::Orig(_)
+            | Operation::End(_)
+            | Operation::If(_)
+            | Operation::Else
+            | Operation::EndIf => return Ok(()),
+        };
+        let label = statement.loc.as_ref().map(|s| s.0.as_str());
+        self.emit(address, word, (file, *line), label);
+        for (offset, word) in following.into_iter().enumerate() {
+            self.emit(address + 1 + offset, word, (file, *line), None);
+        }
+        if let Some((literal_address, value)) = literal {
+            self.emit(literal_address, value, (file, *line), None);
+        }
+        Ok(())
+    }
+
+    /// Lines up every line read, including those of included files, with the words
+    /// it assembled to
+    fn listing(&self, source: &[Line], statements: &[SourceStatement]) -> Listing {
+        let mut statements = statements
+            .iter()
+            .zip(&self.addresses)
+            .enumerate()
+            .peekable();
+        let mut lines = Vec::new();
+
+        for (idx, line) in source.iter().enumerate() {
+            // Lines expanded from a macro show what was assembled rather than what
+            // was written in its body
+            let mut listing_line = ListingLine {
+                line: Some(line.number),
+                expanded: line.expanded.is_some(),
+                address: None,
+                last: None,
+                word: None,
+                source: line.statement().to_string(),
+            };
+            let mut continued = Vec::new();
+            if let Some((statement_idx, (statement, address))) =
+                statements.next_if(|(_, (s, _))| s.index == idx)
+            {
+                let layout = match &statement.statement.op {
+                    Operation::Instruction(_) => WordLayout::Instruction,
+                    Operation::Alf(alf) => WordLayout::Text(alf.chars),
+                    Operation::Text(text) => {
+                        continued = text.words[1..].iter().map(|alf| alf.chars).collect();
+                        WordLayout::Text(text.words[0].chars)
+                    }
+                    _ => WordLayout::Data,
+                };
+                listing_line.address = *address;
+                // A block is listed once, for all of its identical words
+                listing_line.last = self
+                    .blocks
+                    .get(&statement_idx)
+                    .zip(*address)
+                    .map(|(count, address)| address + count - 1);
+                listing_line.word = address
+                    .and_then(|a| self.words.get(&a))
+                    .map(|&word| (word, layout));
+            }
+            // The rest of the words of a TEXT get a line each
+            let first = listing_line.address.filter(|_| listing_line.word.is_some());
+            let expanded = listing_line.expanded;
+            lines.push(listing_line);
+            if let Some(first) = first {
+                for (address, chars) in (first + 1..).zip(continued) {
+                    let Some(&word) = self.words.get(&address) else {
+                        break;
+                    };
+                    lines.push(ListingLine {
+                        line: None,
+                        expanded,
+                        address: Some(address),
+                        last: None,
+                        word: Some((word, WordLayout::Text(chars))),
+                        source: String::new(),
+                    });
+                }
+            }
+        }
+
+        for &address in &self.placed {
+            let Some(&word) = self.words.get(&address) else {
+                continue;
+            };
+            let label = self
+                .debug_info
+                .source_line(address)
+                .and_then(|s| s.label.as_deref())
+                .unwrap_or("");
+            lines.push(ListingLine {
+                line: None,
+                expanded: false,
+                address: Some(address),
+                last: None,
+                word: Some((word, WordLayout::Data)),
+                source: format!("{:<11}CON  {}", label, word.value(BYTE_SIZE)),
+            });
+        }
+        Listing { lines }
+    }
 }
 
-pub struct AssemblerState {
-    output: [MachineWord; N_WORDS],
+/// Packs an instruction into the ±AA I F C layout of a MIX word
+fn encode_instruction(
+    instruction: &MixInstruction,
+    address: i64,
+    env: &Environment,
+) -> Result<Word> {
+    let magnitude = address.abs();
+    if magnitude >= BYTE_SIZE * BYTE_SIZE {
+        bail!("Address {} does not fit in two bytes", address);
+    }
+    let index = instruction.address.index.evaluate(env)?;
+    if !(0..BYTE_SIZE).contains(&index) {
+        bail!("Index {} does not fit in one byte", index);
+    }
+    let negative = (instruction.address.sign == instruction::Sign::Negative) != (address < 0);
+    let sign = if negative { Sign::Minus } else { Sign::Plus };
+
+    Ok(Word::new(
+        sign,
+        [
+            (magnitude / BYTE_SIZE) as u8,
+            (magnitude % BYTE_SIZE) as u8,
+            index as u8,
+            instruction.address.field.evaluate(env)?,
+            instruction.operation_code,
+        ],
+    ))
+}
+
+/// Assembles MIXAL programs, collecting every diagnostic along the way rather
+/// than stopping at the first error
+#[derive(Debug, Default)]
+pub struct Assembler {
+    pub options: Options,
 }
-/// Main entrypoint for assembling a file -- subject to change
-pub fn assemble_file(path: &str) -> Result<()> {
-    let inp_file = File::open(path)?;
-    let reader = BufReader::new(inp_file);
-    let statements: Result<Vec<Statement>> = reader
-        .lines()
-        .map(|line| line?.parse::<Statement>())
-        .collect();
 
-    println!("Parsed {} statements", statements?.len());
-    Ok(())
+impl Assembler {
+    pub fn new(options: Options) -> Self {
+        Self { options }
+    }
+
+    /// Assembles MIXAL source code. `file` labels diagnostics and debug info, and
+    /// files named by INCLUDE are found relative to it.
+    pub fn assemble_str(&self, source: &str, file: &str) -> Program {
+        let mut state = AssemblerState::new(file, &self.options);
+        let mut sources = Sources::default();
+
+        // Line 0 stands for "before the first line"
+        state.position = 0;
+        for definition in &self.options.predefined {
+            let result = definition
+                .parse(self.options.dialect)
+                .and_then(|(name, value)| {
+                    let value = value.evaluate(&state.environment(0))?;
+                    state.define(&name.0, value.value(BYTE_SIZE), SymbolKind::Equ, (file, 0))
+                })
+                .with_context(|| format!("Invalid definition of {}", definition.name));
+            if let Err(err) = result {
+                let message = format!("{:#}", err);
+                let code = Code::InvalidDefinition;
+                state.diagnose(
+                    Severity::Error,
+                    Stage::Assemble,
+                    code,
+                    (file, 0, None),
+                    message,
+                );
+            }
+        }
+
+        let mut statements = Vec::new();
+        let mut pass = FirstPass::default();
+        let (lines, read_errors) = reader::read(
+            file,
+            source,
+            self.options.macros,
+            &mut sources,
+            &mut |idx, line| {
+                if let Some(statement) = state.parse_line(idx, line, !pass.active()) {
+                    statements.push(statement);
+                    state.assign_next_address(&statements, &mut pass);
+                }
+                pass.active()
+            },
+        );
+        for err in read_errors {
+            let message = format!("{:#}", err.error);
+            let at = (err.file.as_str(), err.line, err.span);
+            state.position = err.index + 1;
+            state.diagnose(Severity::Error, Stage::Parse, err.code, at, message);
+            state.note_expansion(err.expansion.as_deref());
+        }
+
+        state.finish_addresses(&statements, pass);
+        state.generate_words(&statements);
+        let listing = state.listing(&lines, &statements);
+
+        // Report diagnostics in the order their lines were read
+        let mut diagnostics: Vec<_> = state.positions.into_iter().zip(state.diagnostics).collect();
+        diagnostics.sort_by_key(|(position, _)| *position);
+
+        Program {
+            image: Image {
+                words: state.words,
+                start: state.start,
+            },
+            symbols: state.symbols.into_iter().collect(),
+            listing,
+            debug_info: state.debug_info,
+            diagnostics: diagnostics.into_iter().map(|(_, d)| d).collect(),
+            sources,
+        }
+    }
+
+    /// Reads all of a MIXAL program from `reader` and assembles it
+    pub fn assemble_reader(&self, mut reader: impl BufRead, file: &str) -> io::Result<Program> {
+        let mut source = String::new();
+        reader.read_to_string(&mut source)?;
+        Ok(self.assemble_str(&source, file))
+    }
+}
+
+/// Assembles MIXAL source code with the default options, failing if there are any errors
+pub fn assemble_source(source: &str, file: &str) -> Result<Program> {
+    let program = Assembler::default().assemble_str(source, file);
+    if program.has_errors() {
+        let errors: Vec<_> = program.errors().map(|e| e.to_string()).collect();
+        bail!("{}", errors.join("\n"));
+    }
+    Ok(program)
+}
+
+/// Assembles a MIXAL file with the default options, failing if there are any errors
+pub fn assemble_file(path: &str) -> Result<Program> {
+    let source = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+    assemble_source(&source, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(sign: u8, bytes: [u8; 5]) -> Word {
+        let sign = if sign == 0 { Sign::Plus } else { Sign::Minus };
+        Word::new(sign, bytes)
+    }
+
+    #[test]
+    fn test_assemble_instructions() {
+        let assembly = assemble_source(
+            " ORIG 100\nSTART LDA 2000,2(0:3)\n JMP START\n ENTA -5\n END START",
+            "test",
+        )
+        .unwrap();
+        assert_eq!(assembly.image.start, Some(100));
+        assert_eq!(assembly.image.words[&100], word(0, [31, 16, 2, 3, 8]));
+        assert_eq!(assembly.image.words[&101], word(0, [1, 36, 0, 0, 39]));
+        assert_eq!(assembly.image.words[&102], word(1, [0, 5, 0, 2, 48]));
+    }
+
+    #[test]
+    fn test_assemble_equ_and_con() {
+        let assembly =
+            assemble_source("X EQU 1000\n ORIG X+1\nC CON 1(1:1),-2\n END C", "test").unwrap();
+        assert_eq!(assembly.image.words[&1001], word(1, [0, 0, 0, 0, 2]));
+        assert_eq!(assembly.image.start, Some(1001));
+    }
+
+    #[test]
+    fn test_assemble_literals_and_undefined_symbols() {
+        let assembly =
+            assemble_source(" LDA =5=\n JMP LATER\n STA =10=\nEND END 0", "test").unwrap();
+        // Two literals and one undefined symbol are placed after the program, in order
+        assert_eq!(assembly.image.words[&0], word(0, [0, 3, 0, 5, 8]));
+        assert_eq!(assembly.image.words[&1], word(0, [0, 4, 0, 0, 39]));
+        assert_eq!(assembly.image.words[&2], word(0, [0, 5, 0, 5, 24]));
+        assert_eq!(assembly.image.words[&3].value(BYTE_SIZE), 5);
+        assert_eq!(assembly.image.words[&4].value(BYTE_SIZE), 0);
+        assert_eq!(assembly.image.words[&5].value(BYTE_SIZE), 10);
+        assert_eq!(
+            assembly.listing.lines[4].source, "           CON  5",
+            "constants placed after END are listed as CONs"
+        );
+        assert_eq!(assembly.listing.lines[5].source, "LATER      CON  0");
+        let end = assembly.debug_info.symbol("END").unwrap();
+        assert_eq!(end.value, 6);
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        assert!(assemble_source("X EQU 1\nX EQU 2", "test").is_err());
+        assert!(assemble_source(" ORIG Y\nY EQU 5", "test").is_err());
+        assert!(assemble_source(" LDA 4096", "test").is_err());
+        assert!(assemble_source(" LDA 1\n BOGUS 1", "prog.mixal").is_err());
+    }
+
+    fn diagnostics(source: &str, options: Options) -> Vec<(Severity, Stage, usize)> {
+        Assembler::new(options)
+            .assemble_str(source, "test")
+            .diagnostics
+            .iter()
+            .map(|d| (d.severity, d.stage, d.line))
+            .collect()
+    }
+
+    #[test]
+    fn test_assembler_collects_every_diagnostic() {
+        let source = " LDA 1\n BOGUS 1\n LDA 1,Z\nX EQU 1\nX EQU 2\n END 0";
+        assert_eq!(
+            diagnostics(source, Options::default()),
+            [
+                (Severity::Error, Stage::Parse, 2),
+                (Severity::Error, Stage::Assemble, 3),
+                (Severity::Error, Stage::Assemble, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_codes_and_spans() {
+        let source = "X EQU 1\nX EQU 2\n LDA 1,Z\n JMP 1+LATER\n bogus\n END 0";
+        let program = Assembler::default().assemble_str(source, "test");
+        let found: Vec<_> = program
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.line, d.span.map(|s| (s.start, s.end))))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (Code::DuplicateSymbol, 2, Some((1, 2))),
+                (Code::InvalidOperand, 3, Some((6, 9))),
+                (Code::UndefinedSymbol, 4, Some((8, 13))),
+                (Code::Syntax, 5, Some((2, 7))),
+            ]
+        );
+        let note = &program.diagnostics[0].notes[0];
+        assert_eq!(
+            (note.line, note.message.as_str()),
+            (1, "symbol first defined here")
+        );
+    }
+
+    #[test]
+    fn test_assemble_include() {
+        let source = " INCLUDE \"include/units.mixal\"\n OUT 0(PRINTER)\nTAPE EQU 1\n END 0";
+        let program = Assembler::default().assemble_str(source, "test_data/main.mixal");
+        assert_eq!(program.image.words[&0], word(0, [0, 0, 0, 18, 37]));
+        assert_eq!(
+            program.debug_info.symbol("PRINTER").unwrap().file,
+            "test_data/include/units.mixal"
+        );
+        assert_eq!(program.listing.lines[3].source, "PRINTER    EQU  18");
+
+        let duplicate = &program.diagnostics[0];
+        assert_eq!(
+            (duplicate.file.as_str(), duplicate.line),
+            ("test_data/main.mixal", 3)
+        );
+        let note = &duplicate.notes[0];
+        assert_eq!(
+            (note.file.as_str(), note.line),
+            ("test_data/include/units.mixal", 2)
+        );
+    }
+
+    #[test]
+    fn test_assemble_macros() {
+        let source = "\
+SAVE MACRO
+%L ST&1 &2
+ ENDM
+ SAVE 1,TMP
+ SAVE 9,TMP
+TMP CON 0
+ END 0";
+        let options = Options {
+            macros: true,
+            ..Options::default()
+        };
+        let program = Assembler::new(options).assemble_str(source, "test");
+        assert_eq!(program.image.words[&0], word(0, [0, 1, 0, 5, 25]));
+        assert_eq!(program.symbols["1ML"], 0);
+        assert_eq!(
+            program.listing.lines[4].to_string(),
+            "   +2  0000  +    1  0  5 25   1ML ST1 TMP"
+        );
+
+        // The error is in the body, and points back to the call
+        let error = &program.diagnostics[0];
+        assert_eq!(
+            (error.line, error.span),
+            (2, Some(Span { start: 4, end: 8 }))
+        );
+        assert_eq!(error.notes[0].message, "in this expansion of macro SAVE");
+        assert_eq!(error.notes[0].line, 5);
+
+        assert!(
+            Assembler::default()
+                .assemble_str(source, "test")
+                .has_errors()
+        );
+    }
+
+    #[test]
+    fn test_assemble_conditionals() {
+        let source = "\
+BINARY EQU 1
+ IF BINARY
+ IF BINARY-1
+ CON 1
+ ELSE
+ CON 2
+ ENDIF
+ ELSE
+ CON 3
+ IF UNDEFINED
+ CON 4
+ ENDIF
+X END 0
+ ENDIF
+ CON 5
+ END 0";
+        let program = assemble_source(source, "test").unwrap();
+        let words: Vec<_> = program
+            .image
+            .words
+            .values()
+            .map(|w| w.value(BYTE_SIZE))
+            .collect();
+        assert_eq!(words, [2, 5]);
+        assert!(!program.symbols.contains_key("X"));
+    }
+
+    #[test]
+    fn test_conditional_directives() {
+        let source = "\
+DEBUG EQU 0
+ IF DEBUG
+ INCLUDE \"include/missing.mixal\"
+DUMP MACRO
+ OUT &1
+ ENDM
+ DUMP 0
+ ELSE
+DUMP MACRO
+ CON &1
+ ENDM
+ ENDIF
+ DUMP 7
+ END 0";
+        let options = Options {
+            macros: true,
+            ..Options::default()
+        };
+        let program = Assembler::new(options).assemble_str(source, "test_data/main.mixal");
+        assert!(!program.has_errors(), "{:?}", program.diagnostics);
+        let words: Vec<_> = program
+            .image
+            .words
+            .values()
+            .map(|w| w.value(BYTE_SIZE))
+            .collect();
+        assert_eq!(words, [7]);
+
+        let source = " IF 0\n INCLUDE \"missing.mixal\"\n ENDIF\n END 0";
+        assert!(assemble_source(source, "test").is_ok());
+        let source = " IF 1\n INCLUDE \"missing.mixal\"\n ENDIF\n END 0";
+        assert!(assemble_source(source, "test").is_err());
+    }
+
+    #[test]
+    fn test_unbalanced_conditionals() {
+        let errors = |source| {
+            Assembler::default()
+                .assemble_str(source, "test")
+                .errors()
+                .map(|d| (d.code, d.line, d.message.clone()))
+                .collect::<Vec<_>>()
+        };
+        let error = |line, message: &str| vec![(Code::Conditional, line, message.to_string())];
+        assert_eq!(
+            errors(" IF 1\n NOP\n END 0"),
+            error(1, "IF has no matching ENDIF")
+        );
+        assert_eq!(
+            errors(" NOP\n ELSE\n END 0"),
+            error(2, "ELSE without a matching IF")
+        );
+        assert_eq!(
+            errors(" ENDIF\n END 0"),
+            error(1, "ENDIF without a matching IF")
+        );
+        assert_eq!(
+            errors(" IF 0\n ELSE\n ELSE\n ENDIF\n END 0"),
+            error(3, "ELSE after the ELSE of the same IF")
+        );
+        assert_eq!(
+            errors("L IF 1\n ENDIF\n END 0"),
+            error(1, "Label 'L' cannot be defined by a conditional")
+        );
+        assert_eq!(errors(" IF Y\n ENDIF\n END 0")[0].0, Code::InvalidOperand);
+    }
+
+    #[test]
+    fn test_assembler_strict_mode() {
+        let source = " JMP LATER\n HLT";
+        let warnings = [
+            (Severity::Warning, Stage::Assemble, 1),
+            (Severity::Warning, Stage::Assemble, 2),
+        ];
+        assert_eq!(diagnostics(source, Options::default()), warnings);
+
+        let strict = Options {
+            strict: true,
+            ..Options::default()
+        };
+        let errors = warnings.map(|(_, stage, line)| (Severity::Error, stage, line));
+        assert_eq!(diagnostics(source, strict), errors);
+    }
+
+    #[test]
+    fn test_assembler_predefined_symbols() {
+        let options = |definitions: &[&str]| Options {
+            predefined: definitions.iter().map(|d| d.parse().unwrap()).collect(),
+            ..Options::default()
+        };
+        let assemble = |definitions: &[&str], source| {
+            Assembler::new(options(definitions)).assemble_str(source, "test")
+        };
+
+        let program = assemble(&["BUF=100", "END=BUF*2+1"], " LDA END\n END 0");
+        assert!(program.diagnostics.is_empty());
+        assert_eq!(program.image.words[&0], word(0, [3, 9, 0, 5, 8]));
+        assert_eq!(program.symbols["BUF"], 100);
+        assert_eq!(program.debug_info.symbol("END").unwrap().line, 0);
+
+        // Definitions are EQUs, so they can't be redefined or refer to the program
+        assert!(assemble(&["BUF=100"], "BUF EQU 5\n END 0").has_errors());
+        assert!(assemble(&["BUF=LATER"], "LATER EQU 5\n END 0").has_errors());
+
+        // Names follow the symbol rules of the dialect
+        assert!(assemble(&["buf=100"], " END 0").has_errors());
+        assert!(assemble(&["LONGERTHAN10=1"], " END 0").has_errors());
+        let mdk = Options {
+            dialect: Dialect::Mdk,
+            ..options(&["long_buffer_size=100", "LONGBUFFERSIZE=long_buffer_size+1"])
+        };
+        let program = Assembler::new(mdk).assemble_str(" end 0", "test");
+        assert!(program.diagnostics.is_empty(), "{:?}", program.diagnostics);
+        assert_eq!(program.symbols["LONGBUFFERSIZE"], 101);
+    }
+
+    #[test]
+    fn test_assemble_mdk_dialect() {
+        let source = "\
+# Sum of a table, GNU MDK style
+table_start equ 100
+        orig 2000
+start   lda table_start,1   load the next entry
+        hlt                 # all done
+message alf \"HI\"
+        alf  A#B~[
+        end start";
+        let mdk = Options {
+            dialect: Dialect::Mdk,
+            ..Options::default()
+        };
+        let program = Assembler::new(mdk).assemble_str(source, "test");
+        assert!(program.diagnostics.is_empty(), "{:?}", program.diagnostics);
+        assert_eq!(program.image.words[&2000], word(0, [1, 36, 1, 5, 8]));
+        assert_eq!(program.image.words[&2001], word(0, [0, 0, 0, 2, 5]));
+        assert_eq!(program.symbols["table_start"], 100);
+        assert_eq!(program.symbols["message"], 2002);
+        assert_eq!(program.image.words[&2002], word(0, [8, 9, 0, 0, 0]));
+        // '#' in unquoted ALF data is Π rather than a comment
+        assert_eq!(program.image.words[&2003], word(0, [1, 21, 2, 10, 20]));
+        assert_eq!(program.image.start, Some(2000));
+
+        // The historical dialect is still the default
+        assert!(
+            Assembler::default()
+                .assemble_str(source, "test")
+                .has_errors()
+        );
+    }
+
+    #[test]
+    fn test_assemble_alf() {
+        let source = " ORIG 100\nMSG ALF  HELLO\n ALF  ΔΣΠ.,\n END MSG";
+        let program = assemble_source(source, "test").unwrap();
+        assert_eq!(program.image.words[&100], word(0, [8, 5, 13, 13, 16]));
+        assert_eq!(program.image.words[&101], word(0, [10, 20, 21, 40, 41]));
+        assert_eq!(
+            program.listing.lines[1].to_string(),
+            "    2  0100  + 08 05 13 13 16  MSG ALF  HELLO  \"HELLO\""
+        );
+    }
+
+    #[test]
+    fn test_assemble_text() {
+        let source = " ORIG 100\nMSG TEXT \"HELLO, WORLD\"\nNEXT CON 1\n END MSG";
+        let program = assemble_source(source, "test").unwrap();
+        assert_eq!(program.image.words[&100], word(0, [8, 5, 13, 13, 16]));
+        assert_eq!(program.image.words[&101], word(0, [41, 0, 26, 16, 19]));
+        assert_eq!(program.image.words[&102], word(0, [13, 4, 0, 0, 0]));
+        assert_eq!(program.symbols["MSG"], 100);
+        assert_eq!(program.symbols["NEXT"], 103);
+        assert_eq!(program.debug_info.source_line(102).unwrap().label, None);
+
+        let listing: Vec<_> = program.listing.lines[1..4]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(
+            listing,
+            [
+                "    2  0100  + 08 05 13 13 16  MSG TEXT \"HELLO, WORLD\"  \"HELLO\"",
+                "       0101  + 41 00 26 16 19  \", WOR\"",
+                "       0102  + 13 04 00 00 00  \"LD   \"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assemble_block() {
+        let source = "\
+SIZE EQU 3
+ ORIG 100
+BUF BLOCK SIZE
+ONES BLOCK 2,1(1:1),-1(0:0)
+AFTER CON 7
+ END 0";
+        let program = assemble_source(source, "test").unwrap();
+        for address in 100..103 {
+            assert_eq!(program.image.words[&address], word(0, [0, 0, 0, 0, 0]));
+        }
+        assert_eq!(program.image.words[&103], word(1, [1, 0, 0, 0, 0]));
+        assert_eq!(program.image.words[&104], word(1, [1, 0, 0, 0, 0]));
+        assert_eq!(program.symbols["BUF"], 100);
+        assert_eq!(program.symbols["ONES"], 103);
+        assert_eq!(program.symbols["AFTER"], 105);
+        assert_eq!(program.debug_info.source_line(101).unwrap().line, 3);
+        assert_eq!(program.debug_info.source_line(101).unwrap().label, None);
+        assert_eq!(
+            program.listing.lines[2].to_string(),
+            "    3  0100  + 00 00 00 00 00  BUF BLOCK SIZE  (0100-0102)"
+        );
+        assert!(
+            program
+                .image
+                .to_string()
+                .contains("0100-0102 + 00 00 00 00 00\n")
+        );
+
+        let error = |source| assemble_source(source, "test").unwrap_err().to_string();
+        assert!(error("X BLOCK 0\n END 0").contains("from 1 to 4000 words"));
+        assert!(error("X BLOCK LATER\nLATER EQU 2\n END 0").contains("LATER"));
+    }
+
+    #[test]
+    fn test_location_out_of_memory() {
+        let errors = |source| {
+            let program = Assembler::default().assemble_str(source, "test");
+            program
+                .errors()
+                .map(|e| (e.code, e.line, e.message.clone()))
+                .collect::<Vec<_>>()
+        };
+        let message = "Location 4000 is outside of memory, which ends at 3999".to_string();
+        assert_eq!(
+            errors(" ORIG 3998\n CON 1\n CON 2\n CON 3\n END 3998"),
+            [(Code::OutOfMemory, 4, message.clone())]
+        );
+        assert_eq!(
+            errors(" ORIG 3995\n BLOCK 10\n END 0"),
+            [(Code::OutOfMemory, 2, message.clone())]
+        );
+        assert_eq!(
+            errors(" ORIG 3999\n LDA =1=\n END 3999"),
+            [(Code::OutOfMemory, 2, message)]
+        );
+        assert_eq!(
+            errors(" ORIG 4100\n END 0"),
+            [(
+                Code::OutOfMemory,
+                1,
+                "Location 4100 is outside of memory, which ends at 3999".to_string()
+            )]
+        );
+        assert_eq!(
+            errors(" ORIG 100\n HLT\n END 5000"),
+            [(
+                Code::OutOfMemory,
+                3,
+                "Location 5000 is outside of memory, which ends at 3999".to_string()
+            )]
+        );
+        // An undefined symbol that doesn't fit is reported once
+        assert_eq!(errors(" ORIG 3999\n LDA X\n END 3999").len(), 1);
+        assert!(errors(" ORIG 3999\n CON 1\n END 3999").is_empty());
+    }
+
+    #[test]
+    fn test_overlapping_words() {
+        let source = "\
+FIRST ORIG 100
+ CON 1
+ BLOCK 5
+ ORIG 101
+ CON 2
+ ORIG 102
+ BLOCK 3,7
+ END 0";
+        let program = Assembler::default().assemble_str(source, "test");
+        assert!(!program.has_errors());
+        assert_eq!(program.image.words[&101], word(0, [0, 0, 0, 0, 2]));
+        assert_eq!(program.image.words[&104], word(0, [0, 0, 0, 0, 7]));
+        let warnings: Vec<_> = program
+            .diagnostics
+            .iter()
+            .map(|d| (d.code, d.line, d.notes[0].line))
+            .collect();
+        // The block overwrites three words, but is reported once
+        assert_eq!(warnings, [(Code::Overlap, 5, 3), (Code::Overlap, 7, 3)]);
+        assert_eq!(
+            program.diagnostics[0].message,
+            "Address 101 is assembled more than once, overwriting the word from line 3"
+        );
+        assert_eq!(
+            program.diagnostics[0].notes[0].message,
+            "first assembled here"
+        );
+
+        let strict = Options {
+            strict: true,
+            ..Options::default()
+        };
+        assert!(
+            Assembler::new(strict)
+                .assemble_str(source, "test")
+                .has_errors()
+        );
+    }
+
+    #[test]
+    fn test_dialect_from_str() {
+        assert_eq!("knuth".parse::<Dialect>().unwrap(), Dialect::Knuth);
+        assert_eq!("mdk".parse::<Dialect>().unwrap(), Dialect::Mdk);
+        assert!("gnu".parse::<Dialect>().is_err());
+    }
+
+    #[test]
+    fn test_definition_from_str() {
+        assert!("BUF=100".parse::<Definition>().is_ok());
+        assert!("BUF=-1(0:2)".parse::<Definition>().is_ok());
+        assert!("buf=100".parse::<Definition>().is_ok());
+        assert!("BUF".parse::<Definition>().is_err());
+        assert!("=100".parse::<Definition>().is_err());
+        assert!("BUF=".parse::<Definition>().is_err());
+    }
+
+    #[test]
+    fn test_assemble_reader() {
+        let source = "START NOP\n END START\n";
+        let program = Assembler::default()
+            .assemble_reader(source.as_bytes(), "test")
+            .unwrap();
+        assert!(!program.has_errors());
+        assert_eq!(program.symbols["START"], 0);
+    }
 }
@@ -0,0 +1,48 @@
+use anyhow::Result;
+
+use super::assemble::Dialect;
+use super::expression::Expression;
+use super::wval::WVal;
+
+/// Extension pseudo-operation that reserves a number of consecutive words, all +0
+/// or all set to a fill W-value, e.g. `BUF BLOCK 100` or `ONES BLOCK 10,1`
+#[derive(Debug, PartialEq)]
+pub struct Block {
+    pub count: Expression,
+    pub fill: Option<WVal>,
+}
+
+impl Block {
+    /// Parses the count and, after a comma, the fill value. An expression can't
+    /// contain a comma, so any further commas belong to the fill W-value.
+    pub fn parse(s: &str, dialect: Dialect) -> Result<Self> {
+        let (count, fill) = match s.trim().split_once(',') {
+            Some((count, fill)) => (count, Some(WVal::parse(fill, dialect)?)),
+            None => (s.trim(), None),
+        };
+        Ok(Block {
+            count: Expression::parse(count, dialect)?,
+            fill,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let block = Block::parse("100", Dialect::Knuth).unwrap();
+        assert_eq!(block.count, "100".parse().unwrap());
+        assert_eq!(block.fill, None);
+
+        let block = Block::parse("N*2,1(0:2),5(3:5)", Dialect::Knuth).unwrap();
+        assert_eq!(block.count, "N*2".parse().unwrap());
+        assert_eq!(block.fill, Some("1(0:2),5(3:5)".parse().unwrap()));
+
+        assert!(Block::parse("", Dialect::Knuth).is_err());
+        assert!(Block::parse("10,", Dialect::Knuth).is_err());
+        assert!(Block::parse("1(0:2)", Dialect::Knuth).is_err());
+    }
+}
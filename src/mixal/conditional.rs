@@ -0,0 +1,8 @@
+use super::expression::Expression;
+
+/// Extension pseudo-operation that assembles the statements up to the matching
+/// ELSE or ENDIF only if the expression is nonzero, and those between ELSE and
+/// ENDIF otherwise
+pub struct If {
+    pub condition: Expression,
+}
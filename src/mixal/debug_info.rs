@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Context, Result, bail};
+
+/// The source line that produced the word at some memory address
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceLine {
+    pub file: String,
+    pub line: usize,
+    /// The symbol in the LOC field of the line, if any
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    /// The symbol names a memory location
+    Label,
+    /// The symbol was given a value with EQU
+    Equ,
+}
+
+/// Where and how a symbol got its value
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolDefinition {
+    pub name: String,
+    pub value: i64,
+    pub kind: SymbolKind,
+    pub file: String,
+    pub line: usize,
+}
+
+/// Debug information produced alongside an assembled program. Maps memory
+/// addresses back to the source that generated them, so tools working on the
+/// machine level can talk about "LOOP+2 (findmax.mixal:8)" instead of 3005.
+///
+/// It is stored as a sidecar text file, one tab-separated record per line:
+///
+/// ```text
+/// line    <address>  <file>  <line>  <label or ->
+/// symbol  <name>  <value>  <label|equ>  <file>  <line>
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct DebugInfo {
+    pub lines: BTreeMap<usize, SourceLine>,
+    pub symbols: Vec<SymbolDefinition>,
+}
+
+impl DebugInfo {
+    pub fn source_line(&self, address: usize) -> Option<&SourceLine> {
+        self.lines.get(&address)
+    }
+
+    pub fn symbol(&self, name: &str) -> Option<&SymbolDefinition> {
+        self.symbols.iter().find(|s| s.name == name)
+    }
+
+    /// Finds the closest label at or before the address, returning it with the
+    /// offset of the address from it
+    pub fn nearest_label(&self, address: usize) -> Option<(&str, usize)> {
+        self.symbols
+            .iter()
+            .filter(|s| s.kind == SymbolKind::Label)
+            .filter_map(|s| {
+                let value = usize::try_from(s.value).ok()?;
+                (value <= address).then(|| (s.name.as_str(), address - value))
+            })
+            .min_by_key(|&(_, offset)| offset)
+    }
+
+    /// Describes an address in terms of the source, e.g. "LOOP+2 (findmax.mixal:8)"
+    pub fn describe(&self, address: usize) -> String {
+        let symbolic = match self.nearest_label(address) {
+            Some((label, 0)) => label.to_string(),
+            Some((label, offset)) => format!("{}+{}", label, offset),
+            None => address.to_string(),
+        };
+        match self.source_line(address) {
+            Some(source) => format!("{} ({}:{})", symbolic, source.file, source.line),
+            None => symbolic,
+        }
+    }
+}
+
+impl fmt::Display for SymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SymbolKind::Label => write!(f, "label"),
+            SymbolKind::Equ => write!(f, "equ"),
+        }
+    }
+}
+
+impl FromStr for SymbolKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "label" => Ok(SymbolKind::Label),
+            "equ" => Ok(SymbolKind::Equ),
+            _ => bail!("Unrecognized symbol kind: {}", s),
+        }
+    }
+}
+
+impl fmt::Display for DebugInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (address, source) in &self.lines {
+            writeln!(
+                f,
+                "line\t{}\t{}\t{}\t{}",
+                address,
+                source.file,
+                source.line,
+                source.label.as_deref().unwrap_or("-")
+            )?;
+        }
+        for symbol in &self.symbols {
+            writeln!(
+                f,
+                "symbol\t{}\t{}\t{}\t{}\t{}",
+                symbol.name, symbol.value, symbol.kind, symbol.file, symbol.line
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl DebugInfo {
+    fn parse_record(&mut self, record: &str) -> Result<()> {
+        match record.split('\t').collect::<Vec<_>>()[..] {
+            ["line", address, file, line, label] => {
+                self.lines.insert(
+                    address.parse()?,
+                    SourceLine {
+                        file: file.to_string(),
+                        line: line.parse()?,
+                        label: (label != "-").then(|| label.to_string()),
+                    },
+                );
+            }
+            ["symbol", name, value, kind, file, line] => self.symbols.push(SymbolDefinition {
+                name: name.to_string(),
+                value: value.parse()?,
+                kind: kind.parse()?,
+                file: file.to_string(),
+                line: line.parse()?,
+            }),
+            _ => bail!("Unrecognized record: {}", record),
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for DebugInfo {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut info = DebugInfo::default();
+        for (idx, record) in s.lines().enumerate().filter(|(_, r)| !r.is_empty()) {
+            info.parse_record(record)
+                .with_context(|| format!("Malformed debug info on line {}", idx + 1))?;
+        }
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> DebugInfo {
+        let line = |line, label: Option<&str>| SourceLine {
+            file: "findmax.mixal".to_string(),
+            line,
+            label: label.map(str::to_string),
+        };
+        let symbol = |name: &str, value, kind| SymbolDefinition {
+            name: name.to_string(),
+            value,
+            kind,
+            file: "findmax.mixal".to_string(),
+            line: 1,
+        };
+        DebugInfo {
+            lines: BTreeMap::from([
+                (3003, line(6, Some("LOOP"))),
+                (3004, line(7, None)),
+                (3005, line(8, None)),
+            ]),
+            symbols: vec![
+                symbol("X", 1000, SymbolKind::Equ),
+                symbol("MAXIMUM", 3000, SymbolKind::Label),
+                symbol("LOOP", 3003, SymbolKind::Label),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_describe() {
+        let info = sample();
+        assert_eq!(info.describe(3003), "LOOP (findmax.mixal:6)");
+        assert_eq!(info.describe(3005), "LOOP+2 (findmax.mixal:8)");
+        assert_eq!(info.describe(3001), "MAXIMUM+1");
+        // EQU symbols are values, not locations, so they never label an address
+        assert_eq!(info.describe(1000), "1000");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let info = sample();
+        assert_eq!(info.to_string().parse::<DebugInfo>().unwrap(), info);
+    }
+
+    #[test]
+    fn test_from_str_errors() {
+        assert!("line\t3000\tfile".parse::<DebugInfo>().is_err());
+        assert!("line\tX\tfile\t1\t-".parse::<DebugInfo>().is_err());
+        assert!("symbol\tX\t1\tbogus\tfile\t1".parse::<DebugInfo>().is_err());
+    }
+}
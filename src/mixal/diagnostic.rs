@@ -0,0 +1,524 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::ops::Range;
+
+use serde_json::{Value, json};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// Which step of assembly a diagnostic came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    /// Reading a line into a Statement
+    Parse,
+    /// Assigning addresses and generating words
+    Assemble,
+}
+
+/// The kind of problem a diagnostic reports. Each has a stable identifier that
+/// tools can match on, unlike the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Code {
+    /// A line that isn't a valid MIXAL statement
+    Syntax,
+    /// A symbol defined by more than one statement
+    DuplicateSymbol,
+    /// An operand that can't be evaluated or doesn't fit where it goes
+    InvalidOperand,
+    /// A symbol defined from outside the program that can't be evaluated
+    InvalidDefinition,
+    /// An INCLUDE that can't be followed
+    Include,
+    /// A macro that can't be defined or expanded
+    Macro,
+    /// An IF, ELSE or ENDIF that doesn't match up with the others
+    Conditional,
+    /// A word assembled outside of the 4000 words of memory
+    OutOfMemory,
+    /// A symbol that is used but never defined
+    UndefinedSymbol,
+    /// A statement after END
+    AfterEnd,
+    /// A program without an END statement
+    MissingEnd,
+    /// A word assembled by more than one statement
+    Overlap,
+}
+
+impl Code {
+    pub const ALL: [Code; 12] = [
+        Code::Syntax,
+        Code::DuplicateSymbol,
+        Code::InvalidOperand,
+        Code::InvalidDefinition,
+        Code::Include,
+        Code::Macro,
+        Code::Conditional,
+        Code::OutOfMemory,
+        Code::UndefinedSymbol,
+        Code::AfterEnd,
+        Code::MissingEnd,
+        Code::Overlap,
+    ];
+
+    /// The stable identifier, e.g. "E0002"
+    pub fn id(&self) -> &'static str {
+        match self {
+            Code::Syntax => "E0001",
+            Code::DuplicateSymbol => "E0002",
+            Code::InvalidOperand => "E0003",
+            Code::InvalidDefinition => "E0004",
+            Code::Include => "E0005",
+            Code::Macro => "E0006",
+            Code::Conditional => "E0007",
+            Code::OutOfMemory => "E0008",
+            Code::UndefinedSymbol => "W0001",
+            Code::AfterEnd => "W0002",
+            Code::MissingEnd => "W0003",
+            Code::Overlap => "W0004",
+        }
+    }
+
+    /// A short human readable name, e.g. "duplicate-symbol"
+    pub fn name(&self) -> &'static str {
+        match self {
+            Code::Syntax => "syntax",
+            Code::DuplicateSymbol => "duplicate-symbol",
+            Code::InvalidOperand => "invalid-operand",
+            Code::InvalidDefinition => "invalid-definition",
+            Code::Include => "include",
+            Code::Macro => "macro",
+            Code::Conditional => "conditional",
+            Code::OutOfMemory => "out-of-memory",
+            Code::UndefinedSymbol => "undefined-symbol",
+            Code::AfterEnd => "after-end",
+            Code::MissingEnd => "missing-end",
+            Code::Overlap => "overlap",
+        }
+    }
+}
+
+/// A range of columns on a line. Columns count characters from 1, and `end` is
+/// one past the last column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    /// The span of the byte range `start..end` of `line`
+    pub fn from_bytes(line: &str, start: usize, end: usize) -> Self {
+        let column = |byte: usize| line[..byte].chars().count() + 1;
+        Span {
+            start: column(start),
+            end: column(end),
+        }
+    }
+
+    /// The byte range of `line` covered by this span
+    pub fn bytes(&self, line: &str) -> Range<usize> {
+        let byte = |column: usize| {
+            line.char_indices()
+                .nth(column - 1)
+                .map_or(line.len(), |(idx, _)| idx)
+        };
+        byte(self.start)..byte(self.end)
+    }
+
+    /// The text of `line` covered by this span
+    pub fn slice<'a>(&self, line: &'a str) -> &'a str {
+        &line[self.bytes(line)]
+    }
+
+    /// The span of the first occurrence of `needle` within this span of `line`
+    pub fn find(&self, line: &str, needle: &str) -> Option<Span> {
+        let text = self.slice(line);
+        let byte = text.find(needle)?;
+        let start = self.start + text[..byte].chars().count();
+        Some(Span {
+            start,
+            end: start + needle.chars().count(),
+        })
+    }
+}
+
+/// Extra information attached to a diagnostic, e.g. where a symbol was first defined
+#[derive(Debug, Clone, PartialEq)]
+pub struct Note {
+    pub message: String,
+    pub file: String,
+    /// The line the note points at, or 0 if it doesn't point at a line
+    pub line: usize,
+    pub span: Option<Span>,
+}
+
+/// An error or warning about a line of a MIXAL program
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub stage: Stage,
+    pub code: Code,
+    pub file: String,
+    /// The line the problem is on, or 0 if it isn't on any line of the program
+    pub line: usize,
+    /// The columns of the line at fault, if known
+    pub span: Option<Span>,
+    pub message: String,
+    pub notes: Vec<Note>,
+}
+
+/// The text of every file a program was assembled from, so diagnostics can show
+/// the lines they point at
+#[derive(Debug, Clone, Default)]
+pub struct Sources {
+    files: BTreeMap<String, String>,
+}
+
+impl Sources {
+    pub fn add(&mut self, file: &str, text: &str) {
+        self.files.insert(file.to_string(), text.to_string());
+    }
+
+    /// The text of a line, counting from 1
+    pub fn line(&self, file: &str, line: usize) -> Option<&str> {
+        self.files.get(file)?.lines().nth(line.checked_sub(1)?)
+    }
+}
+
+/// ANSI escape codes for the parts of a rendered diagnostic
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const GREEN: &str = "\x1b[1;32m";
+const BLUE: &str = "\x1b[1;34m";
+
+impl Diagnostic {
+    /// Renders the diagnostic the way rustc does, with the source line it points at
+    /// and carets under the columns at fault, followed by any notes
+    pub fn render(&self, sources: &Sources, color: bool) -> String {
+        let paint = |style: &str, text: &str| {
+            if color {
+                format!("{}{}{}", style, text, RESET)
+            } else {
+                text.to_string()
+            }
+        };
+        let (style, caret_style) = match self.severity {
+            Severity::Error => (RED, RED),
+            Severity::Warning => (YELLOW, YELLOW),
+        };
+        let gutter = std::iter::once(self.line)
+            .chain(self.notes.iter().map(|n| n.line))
+            .max()
+            .unwrap_or(0)
+            .to_string()
+            .len();
+
+        let mut out = String::new();
+        let heading = format!("{}[{}]", self.severity, self.code.id());
+        let message = paint(BOLD, &format!(": {}", self.message));
+        let _ = writeln!(out, "{}{}", paint(style, &heading), message);
+        let at = Snippet {
+            file: &self.file,
+            line: self.line,
+            span: self.span,
+        };
+        at.render(&mut out, sources, gutter, caret_style, &paint);
+
+        for note in &self.notes {
+            let message = paint(BOLD, &format!(": {}", note.message));
+            let _ = writeln!(out, "{}{}", paint(GREEN, "note"), message);
+            let at = Snippet {
+                file: &note.file,
+                line: note.line,
+                span: note.span,
+            };
+            at.render(&mut out, sources, gutter, GREEN, &paint);
+        }
+        out
+    }
+}
+
+/// A location to show in a rendered diagnostic
+struct Snippet<'a> {
+    file: &'a str,
+    line: usize,
+    span: Option<Span>,
+}
+
+impl Snippet<'_> {
+    fn render(
+        &self,
+        out: &mut String,
+        sources: &Sources,
+        gutter: usize,
+        caret_style: &str,
+        paint: &dyn Fn(&str, &str) -> String,
+    ) {
+        let arrow = paint(BLUE, &format!("{:>width$}", "-->", width = gutter + 3));
+        let _ = match (self.line, self.span) {
+            (0, _) => writeln!(out, "{} {}", arrow, self.file),
+            (line, None) => writeln!(out, "{} {}:{}", arrow, self.file, line),
+            (line, Some(span)) => writeln!(out, "{} {}:{}:{}", arrow, self.file, line, span.start),
+        };
+        let Some(text) = sources.line(self.file, self.line) else {
+            return;
+        };
+
+        let bar = paint(BLUE, &format!("{:>width$} |", "", width = gutter));
+        let number = paint(BLUE, &format!("{:>width$} |", self.line, width = gutter));
+        let _ = writeln!(out, "{}", bar);
+        let _ = writeln!(out, "{} {}", number, text);
+        if let Some(span) = self.span {
+            // Keep tabs so the carets line up with the text above
+            let indent: String = text
+                .chars()
+                .take(span.start - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let carets = "^".repeat(span.end.saturating_sub(span.start).max(1));
+            let _ = writeln!(out, "{} {}{}", bar, indent, paint(caret_style, &carets));
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:", self.file, self.line)?;
+        if let Some(span) = self.span {
+            write!(f, "{}:", span.start)?;
+        }
+        write!(
+            f,
+            " {}[{}]: {}",
+            self.severity,
+            self.code.id(),
+            self.message
+        )
+    }
+}
+
+/// Renders diagnostics as a JSON array with one object per diagnostic
+pub fn to_json(diagnostics: &[Diagnostic]) -> String {
+    let diagnostics: Vec<Value> = diagnostics
+        .iter()
+        .map(|d| {
+            json!({
+                "severity": d.severity.to_string(),
+                "code": d.code.id(),
+                "name": d.code.name(),
+                "file": d.file,
+                "line": d.line,
+                "start_column": d.span.map(|s| s.start),
+                "end_column": d.span.map(|s| s.end),
+                "message": d.message,
+                "notes": d.notes.iter().map(|n| json!({
+                    "file": n.file,
+                    "line": n.line,
+                    "start_column": n.span.map(|s| s.start),
+                    "end_column": n.span.map(|s| s.end),
+                    "message": n.message,
+                })).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    Value::Array(diagnostics).to_string()
+}
+
+fn sarif_location(file: &str, line: usize, span: Option<Span>) -> Value {
+    let mut location = json!({ "artifactLocation": { "uri": file } });
+    // SARIF lines count from 1, so there is no region for line 0
+    if line > 0 {
+        location["region"] = json!({ "startLine": line });
+        if let Some(span) = span {
+            location["region"]["startColumn"] = json!(span.start);
+            location["region"]["endColumn"] = json!(span.end);
+        }
+    }
+    location
+}
+
+/// Renders diagnostics as a SARIF 2.1.0 log, as understood by code scanning tools
+pub fn to_sarif(diagnostics: &[Diagnostic]) -> String {
+    let rules: Vec<Value> = Code::ALL
+        .iter()
+        .map(|code| json!({ "id": code.id(), "name": code.name() }))
+        .collect();
+    let results: Vec<Value> = diagnostics
+        .iter()
+        .map(|d| {
+            let related: Vec<Value> = d
+                .notes
+                .iter()
+                .map(|n| {
+                    json!({
+                        "physicalLocation": sarif_location(&n.file, n.line, n.span),
+                        "message": { "text": n.message },
+                    })
+                })
+                .collect();
+            json!({
+                "ruleId": d.code.id(),
+                "level": d.severity.to_string(),
+                "message": { "text": d.message },
+                "locations": [{ "physicalLocation": sarif_location(&d.file, d.line, d.span) }],
+                "relatedLocations": related,
+            })
+        })
+        .collect();
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "mix",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": results,
+        }],
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(line: usize, span: Option<Span>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            stage: Stage::Assemble,
+            code: Code::DuplicateSymbol,
+            file: "prog.mixal".to_string(),
+            line,
+            span,
+            message: "Symbol 'X' is defined more than once".to_string(),
+            notes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_span() {
+        let line = "LOOP LDA X,1";
+        let address = Span::from_bytes(line, 9, 12);
+        assert_eq!(address, Span { start: 10, end: 13 });
+        assert_eq!(address.find(line, "1"), Some(Span { start: 12, end: 13 }));
+        assert_eq!(address.find(line, "LOOP"), None);
+        assert_eq!(address.slice(line), "X,1");
+        assert_eq!(Span::from_bytes("Δ X", 2, 3), Span { start: 2, end: 3 });
+    }
+
+    #[test]
+    fn test_display() {
+        let span = Some(Span { start: 1, end: 2 });
+        assert_eq!(
+            diagnostic(5, span).to_string(),
+            "prog.mixal:5:1: error[E0002]: Symbol 'X' is defined more than once"
+        );
+        assert_eq!(
+            diagnostic(5, None).to_string(),
+            "prog.mixal:5: error[E0002]: Symbol 'X' is defined more than once"
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        let json: Value =
+            serde_json::from_str(&to_json(&[diagnostic(5, Some(Span { start: 1, end: 2 }))]))
+                .unwrap();
+        assert_eq!(
+            json,
+            json!([{
+                "severity": "error",
+                "code": "E0002",
+                "name": "duplicate-symbol",
+                "file": "prog.mixal",
+                "line": 5,
+                "start_column": 1,
+                "end_column": 2,
+                "message": "Symbol 'X' is defined more than once",
+                "notes": [],
+            }])
+        );
+    }
+
+    #[test]
+    fn test_to_sarif() {
+        let diagnostics = [
+            diagnostic(5, Some(Span { start: 1, end: 2 })),
+            diagnostic(0, None),
+        ];
+        let sarif: Value = serde_json::from_str(&to_sarif(&diagnostics)).unwrap();
+        let results = &sarif["runs"][0]["results"];
+        assert_eq!(results[0]["ruleId"], "E0002");
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"]["region"],
+            json!({ "startLine": 5, "startColumn": 1, "endColumn": 2 })
+        );
+        assert!(results[1]["locations"][0]["physicalLocation"]["region"].is_null());
+    }
+
+    #[test]
+    fn test_render() {
+        let mut sources = Sources::default();
+        sources.add("prog.mixal", "X EQU 1\n LDA 1\nX EQU 2\n");
+        let mut duplicate = diagnostic(3, Some(Span { start: 1, end: 2 }));
+        duplicate.notes.push(Note {
+            message: "symbol first defined here".to_string(),
+            file: "prog.mixal".to_string(),
+            line: 1,
+            span: Some(Span { start: 1, end: 2 }),
+        });
+        assert_eq!(
+            duplicate.render(&sources, false),
+            "\
+error[E0002]: Symbol 'X' is defined more than once
+ --> prog.mixal:3:1
+  |
+3 | X EQU 2
+  | ^
+note: symbol first defined here
+ --> prog.mixal:1:1
+  |
+1 | X EQU 1
+  | ^
+"
+        );
+
+        let colored = duplicate.render(&sources, true);
+        assert!(colored.starts_with("\x1b[1;31merror[E0002]\x1b[0m"));
+
+        // Without a span there is nothing to underline, and without a line nothing to show
+        assert_eq!(
+            diagnostic(2, None).render(&sources, false),
+            "\
+error[E0002]: Symbol 'X' is defined more than once
+ --> prog.mixal:2
+  |
+2 |  LDA 1
+"
+        );
+        assert_eq!(
+            diagnostic(0, None).render(&sources, false),
+            "error[E0002]: Symbol 'X' is defined more than once\n --> prog.mixal\n"
+        );
+    }
+}
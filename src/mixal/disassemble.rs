@@ -0,0 +1,232 @@
+use crate::word::Word;
+
+use super::assemble::BYTE_SIZE;
+use super::debug_info::DebugInfo;
+use super::image::Image;
+use super::instruction::OPCODES;
+
+/// Operation codes whose F-part picks a variant of the operation (e.g. JMP vs. JL)
+/// rather than being a field specifier, unit number or count
+fn field_selects_variant(operation_code: u8) -> bool {
+    matches!(operation_code, 5 | 6 | 39..=55)
+}
+
+/// Operation codes whose address part is a value rather than a memory location, so
+/// it shouldn't be replaced by a label
+fn address_is_value(operation_code: u8) -> bool {
+    matches!(operation_code, 5 | 6 | 48..=55)
+}
+
+/// The numeric value of a default field from the opcode table, e.g. "0:5" -> 5
+fn default_field_value(default_field: &str) -> u8 {
+    match default_field.split_once(':') {
+        Some((left, right)) => 8 * left.parse::<u8>().unwrap() + right.parse::<u8>().unwrap(),
+        None => default_field.parse().unwrap(),
+    }
+}
+
+/// Finds the mnemonic for an operation code and F-part, along with the field
+/// specifier that has to be written out, if it differs from the default.
+/// Returns None if no instruction has this combination.
+fn decode(operation_code: u8, field: u8) -> Option<(&'static str, Option<String>)> {
+    let mut candidates = OPCODES.iter().filter(|(_, c, _)| *c == operation_code);
+
+    if let Some((mnemonic, _, _)) = candidates
+        .clone()
+        .find(|(_, _, default)| default_field_value(default) == field)
+    {
+        return Some((mnemonic, None));
+    }
+    if field_selects_variant(operation_code) {
+        return None;
+    }
+
+    // The first entry for an operation code is its ordinary form, e.g. ADD rather than FADD
+    let (mnemonic, _, default) = candidates.next()?;
+    if default.contains(':') {
+        let (left, right) = (field / 8, field % 8);
+        if left > right || right > 5 {
+            return None;
+        }
+        Some((mnemonic, Some(format!("({}:{})", left, right))))
+    } else {
+        Some((mnemonic, Some(format!("({})", field))))
+    }
+}
+
+/// Finds the label of the word assembled at a memory location, if there is one.
+/// Labels of statements that don't emit a word (e.g. `BUF ORIG *+10`) never appear
+/// in the LOC field of the output, so they can't be used in addresses either.
+fn label_for(address: i64, debug_info: Option<&DebugInfo>) -> Option<&str> {
+    debug_info?
+        .source_line(usize::try_from(address).ok()?)?
+        .label
+        .as_deref()
+}
+
+/// Turns a word back into the OP and ADDRESS fields of a MIXAL statement. Words
+/// that aren't valid instructions come back as a CON of their value.
+pub fn disassemble_word(word: &Word, debug_info: Option<&DebugInfo>) -> (String, String) {
+    let [a1, a2, index, field, operation_code] = word.bytes;
+    let constant = || {
+        let sign = if word.is_minus_zero() { "-" } else { "" };
+        (
+            "CON".to_string(),
+            format!("{}{}", sign, word.value(BYTE_SIZE)),
+        )
+    };
+
+    // A zero word is almost certainly data rather than "NOP 0(0:0)"
+    if *word == Word::ZERO || index > 6 {
+        return constant();
+    }
+    let Some((mnemonic, field)) = decode(operation_code, field) else {
+        return constant();
+    };
+
+    let magnitude = a1 as i64 * BYTE_SIZE + a2 as i64;
+    let mut address = match label_for(magnitude, debug_info) {
+        Some(label) if !word.is_negative() && !address_is_value(operation_code) => {
+            label.to_string()
+        }
+        _ => format!("{}{}", if word.is_negative() { "-" } else { "" }, magnitude),
+    };
+    if index != 0 {
+        address += &format!(",{}", index);
+    }
+    if let Some(field) = field {
+        address += &field;
+    }
+    if address == "0" {
+        address.clear();
+    }
+    (mnemonic.to_string(), address)
+}
+
+/// Formats one line of MIXAL with aligned LOC, OP and ADDRESS columns
+fn statement(loc: &str, op: &str, address: &str) -> String {
+    format!("{:<11}{:<5}{}", loc, op, address)
+        .trim_end()
+        .to_string()
+}
+
+/// Turns a memory image back into a MIXAL program that assembles to the same image.
+/// Labels are taken from the debug info when it is available.
+pub fn disassemble(image: &Image, debug_info: Option<&DebugInfo>) -> String {
+    let mut lines = Vec::new();
+    let mut next_address = None;
+
+    for (&address, word) in &image.words {
+        if next_address != Some(address) {
+            lines.push(statement("", "ORIG", &address.to_string()));
+        }
+        let loc = debug_info
+            .and_then(|info| info.source_line(address))
+            .and_then(|source| source.label.as_deref())
+            .unwrap_or("");
+        let (op, operand) = disassemble_word(word, debug_info);
+        lines.push(statement(loc, &op, &operand));
+        next_address = Some(address + 1);
+    }
+
+    if let Some(start) = image.start {
+        let start = label_for(start as i64, debug_info)
+            .map(str::to_string)
+            .unwrap_or_else(|| start.to_string());
+        lines.push(statement("", "END", &start));
+    }
+
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mixal::assemble::assemble_source;
+
+    fn instruction(sign: u8, address: u16, index: u8, field: u8, code: u8) -> Word {
+        Word::from_bytes([
+            sign,
+            (address / 64) as u8,
+            (address % 64) as u8,
+            index,
+            field,
+            code,
+        ])
+    }
+
+    fn dis(word: Word) -> String {
+        let (op, address) = disassemble_word(&word, None);
+        format!("{} {}", op, address).trim_end().to_string()
+    }
+
+    #[test]
+    fn test_variants() {
+        assert_eq!(dis(instruction(0, 3000, 0, 4, 39)), "JL 3000");
+        assert_eq!(dis(instruction(0, 3000, 0, 0, 39)), "JMP 3000");
+        assert_eq!(dis(instruction(0, 0, 0, 2, 5)), "HLT");
+        assert_eq!(dis(instruction(1, 5, 0, 2, 48)), "ENTA -5");
+        assert_eq!(dis(instruction(0, 0, 1, 2, 51)), "ENT3 0,1");
+        assert_eq!(dis(instruction(0, 100, 0, 6, 1)), "FADD 100");
+        assert_eq!(dis(instruction(0, 0, 0, 7, 5)), "FIX");
+        assert_eq!(dis(instruction(0, 3, 0, 6, 6)), "SLB 3");
+        assert_eq!(dis(instruction(0, 3000, 0, 7, 47)), "JXO 3000");
+    }
+
+    #[test]
+    fn test_fields() {
+        assert_eq!(dis(instruction(0, 2000, 2, 3, 8)), "LDA 2000,2(0:3)");
+        assert_eq!(dis(instruction(0, 2000, 0, 5, 8)), "LDA 2000");
+        assert_eq!(dis(instruction(0, 2000, 0, 2, 32)), "STJ 2000");
+        assert_eq!(dis(instruction(0, 2000, 0, 5, 32)), "STJ 2000(0:5)");
+        assert_eq!(dis(instruction(0, 1000, 0, 16, 37)), "OUT 1000(16)");
+    }
+
+    #[test]
+    fn test_constants() {
+        assert_eq!(dis(Word::ZERO), "CON 0");
+        // No JMP variant has F = 10
+        assert_eq!(dis(instruction(0, 0, 0, 10, 39)), "CON 679");
+        // Fields must have L <= R <= 5
+        assert_eq!(dis(instruction(1, 0, 0, 7, 8)), "CON -456");
+        // There is no index register 7
+        assert_eq!(dis(instruction(0, 0, 7, 5, 8)), "CON 29000");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let source =
+            "X EQU 1000\n ORIG 3000\nSTART LDA X,3(1:3)\n JMP START\n CON -1000000\n END START";
+        let assembly = assemble_source(source, "test").unwrap();
+        let text = disassemble(&assembly.image, Some(&assembly.debug_info));
+        let expected = [
+            "           ORIG 3000",
+            "START      LDA  1000,3(1:3)",
+            "           JMP  START",
+            "           CON  -1000000",
+            "           END  START",
+        ];
+        assert_eq!(text.lines().collect::<Vec<_>>(), expected);
+        let reassembled = assemble_source(&text, "dis").unwrap();
+        assert_eq!(reassembled.image, assembly.image);
+    }
+
+    #[test]
+    fn test_round_trip_orig_label() {
+        let source = " ORIG 3000\nSTART LDA BUF\n STA BUF+1\n JMP START\nBUF ORIG *+10\nTOP EQU 3000\n JMP TOP\n END START";
+        let assembly = assemble_source(source, "test").unwrap();
+        let text = disassemble(&assembly.image, Some(&assembly.debug_info));
+        let expected = [
+            "           ORIG 3000",
+            "START      LDA  3003",
+            "           STA  3004",
+            "           JMP  START",
+            "           ORIG 3013",
+            "           JMP  START",
+            "           END  START",
+        ];
+        assert_eq!(text.lines().collect::<Vec<_>>(), expected);
+        let reassembled = assemble_source(&text, "dis").unwrap();
+        assert_eq!(reassembled.image, assembly.image);
+    }
+}
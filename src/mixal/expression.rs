@@ -1,5 +1,9 @@
+use std::collections::HashMap;
 use std::str::FromStr;
 
+use anyhow::{Result, anyhow, bail};
+
+use crate::mixal::assemble::{Dialect, WORD_SIZE};
 use crate::mixal::operator::UnaryOperator;
 
 use super::number::Number;
@@ -12,7 +16,7 @@ use super::symbol::Symbol;
 ///     A symbol -- which is a name that points to some other value
 ///     A number -- which is a string of at most 10 digits
 ///     A binary or unary operator -- which recursively contain expressions that the operators act on
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
     Asterisk,
     Symbol(Symbol),
@@ -21,31 +25,83 @@ pub enum Expression {
     UnaryOperation(UnaryOperator, Box<Expression>),
 }
 
-impl FromStr for Expression {
-    type Err = anyhow::Error;
+/// The values an expression can refer to while it is being evaluated
+pub struct Environment<'a> {
+    /// Every symbol defined so far
+    pub symbols: &'a HashMap<String, i64>,
+    /// The value of "*"
+    pub location: i64,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Expression {
+    /// Evaluates the expression strictly left to right, as MIXAL has no operator precedence
+    pub fn evaluate(&self, env: &Environment) -> Result<i64> {
+        let value = match self {
+            Expression::Asterisk => env.location,
+            Expression::Symbol(symbol) => *env
+                .symbols
+                .get(&symbol.0)
+                .ok_or_else(|| anyhow!("Undefined symbol: {}", symbol.0))?,
+            Expression::Number(number) => number.0 as i64,
+            Expression::UnaryOperation(op, expr) => op.apply(expr.evaluate(env)?),
+            Expression::BinaryOperation(op, left, right) => {
+                op.apply(left.evaluate(env)?, right.evaluate(env)?)?
+            }
+        };
+
+        if value.abs() >= WORD_SIZE {
+            bail!("Expression value {} does not fit in a MIX word", value);
+        }
+        Ok(value)
+    }
+
+    /// Every symbol referenced by the expression, from left to right
+    pub fn symbols(&self) -> Vec<&Symbol> {
+        match self {
+            Expression::Asterisk | Expression::Number(_) => vec![],
+            Expression::Symbol(symbol) => vec![symbol],
+            Expression::UnaryOperation(_, expr) => expr.symbols(),
+            Expression::BinaryOperation(_, left, right) => {
+                let mut symbols = left.symbols();
+                symbols.extend(right.symbols());
+                symbols
+            }
+        }
+    }
+}
+
+impl Expression {
+    /// Parses an expression whose symbols follow the rules of `dialect`
+    pub fn parse(s: &str, dialect: Dialect) -> Result<Self> {
         if s == "*" {
             Ok(Expression::Asterisk)
         } else if let Some((pos, op)) = BinaryOperator::find_rightmost_in(s) {
             Ok(Expression::BinaryOperation(
                 s[pos..pos + op.len()].parse()?,
-                Box::new(s[0..pos].parse()?),
-                Box::new(s[pos + 1..].parse()?),
+                Box::new(Expression::parse(&s[0..pos], dialect)?),
+                Box::new(Expression::parse(&s[pos + op.len()..], dialect)?),
             ))
         } else if UnaryOperator::starts_with(s) {
             Ok(Expression::UnaryOperation(
                 s[0..1].parse()?,
-                Box::new(s[1..].parse()?),
+                Box::new(Expression::parse(&s[1..], dialect)?),
             ))
         } else if s.chars().all(|c| c.is_ascii_digit()) {
             Ok(Expression::Number(s.parse()?))
         } else {
-            Ok(Expression::Symbol(s.parse()?))
+            Ok(Expression::Symbol(Symbol::parse(s, dialect)?))
         }
     }
 }
 
+impl FromStr for Expression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expression::parse(s, Dialect::Knuth)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,6 +191,39 @@ mod tests {
         );
     }
 
+    fn eval(s: &str) -> Result<i64> {
+        let symbols = HashMap::from([("X".to_string(), 1000), ("Y".to_string(), -3)]);
+        let env = Environment {
+            symbols: &symbols,
+            location: 3000,
+        };
+        s.parse::<Expression>()?.evaluate(&env)
+    }
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(eval("34").unwrap(), 34);
+        assert_eq!(eval("-1+5*20/6").unwrap(), 13);
+        assert_eq!(eval("X+1").unwrap(), 1001);
+        assert_eq!(eval("-X").unwrap(), -1000);
+        assert_eq!(eval("*-5").unwrap(), 2995);
+        assert_eq!(eval("**2").unwrap(), 6000);
+        assert_eq!(eval("X*Y").unwrap(), -3000);
+        assert_eq!(eval("1:3").unwrap(), 11);
+        assert_eq!(eval("1//2").unwrap(), WORD_SIZE / 2);
+
+        assert!(eval("Z").is_err());
+        assert!(eval("5/0").is_err());
+        assert!(eval("1073741824").is_err());
+    }
+
+    #[test]
+    fn test_symbols() {
+        let expr: Expression = "X+2*Y-X".parse().unwrap();
+        let names: Vec<_> = expr.symbols().into_iter().map(|s| s.0.as_str()).collect();
+        assert_eq!(names, ["X", "Y", "X"]);
+    }
+
     #[test]
     fn test_from_str_errors() {
         // Empty string
@@ -2,11 +2,12 @@ use std::str::FromStr;
 
 use anyhow::Result;
 
-use crate::mixal::number::Number;
+use crate::mixal::assemble::{BYTE_SIZE, Dialect};
+use crate::word::FieldSpec;
 
-use super::expression::Expression;
+use super::expression::{Environment, Expression};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Field {
     expression: Expression,
 }
@@ -15,9 +16,13 @@ impl Field {
     /// Looks for an opening parenthesis in the given string, and parses the portion
     /// of the string from that index as a Field specifier, otherwise returns the
     /// default field specifier, which is equivalent to (0:5).
-    pub fn find_field_or_default(s: &str, default: &str) -> Result<(Self, usize), anyhow::Error> {
+    pub fn find_field_or_default(
+        s: &str,
+        default: &str,
+        dialect: Dialect,
+    ) -> Result<(Self, usize), anyhow::Error> {
         if let Some(idx) = s.find('(') {
-            Ok((s[idx..].parse()?, idx))
+            Ok((Field::parse(&s[idx..], dialect)?, idx))
         } else {
             Ok((
                 Self {
@@ -27,22 +32,43 @@ impl Field {
             ))
         }
     }
-}
 
-impl FromStr for Field {
-    type Err = anyhow::Error;
+    /// Evaluates the field specifier to the value of the F byte
+    pub fn evaluate(&self, env: &Environment) -> Result<u8> {
+        let value = self.expression.evaluate(env)?;
+        if !(0..BYTE_SIZE).contains(&value) {
+            anyhow::bail!("Field specifier {} does not fit in one byte", value);
+        }
+        Ok(value as u8)
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    /// Evaluates the field specifier as a partial field (L:R) of a word
+    pub fn bounds(&self, env: &Environment) -> Result<FieldSpec> {
+        FieldSpec::from_byte(self.evaluate(env)?)
+    }
+}
+
+impl Field {
+    /// Parses a parenthesized field specifier like "(1:3)"
+    pub fn parse(s: &str, dialect: Dialect) -> Result<Self> {
         if !(s.len() > 2 && s.starts_with('(') && s.ends_with(')')) {
             anyhow::bail!("Invalid field specifier: {}", s);
         }
 
         Ok(Field {
-            expression: s[1..s.len() - 1].parse()?,
+            expression: Expression::parse(&s[1..s.len() - 1], dialect)?,
         })
     }
 }
 
+impl FromStr for Field {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Field::parse(s, Dialect::Knuth)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,7 +103,7 @@ mod tests {
     #[test]
     fn test_find_field_or_default() {
         assert_eq!(
-            Field::find_field_or_default("3+4(1:1)", "0:5").unwrap(),
+            Field::find_field_or_default("3+4(1:1)", "0:5", Dialect::Knuth).unwrap(),
             (
                 Field {
                     expression: "1:1".parse().unwrap()
@@ -86,7 +112,7 @@ mod tests {
             )
         );
         assert_eq!(
-            Field::find_field_or_default("10024(3:5)", "4").unwrap(),
+            Field::find_field_or_default("10024(3:5)", "4", Dialect::Knuth).unwrap(),
             (
                 Field {
                     expression: "3:5".parse().unwrap()
@@ -95,7 +121,7 @@ mod tests {
             )
         );
         assert_eq!(
-            Field::find_field_or_default("10024", "0:5").unwrap(),
+            Field::find_field_or_default("10024", "0:5", Dialect::Knuth).unwrap(),
             (
                 Field {
                     expression: "0:5".parse().unwrap()
@@ -104,7 +130,7 @@ mod tests {
             )
         );
         assert_eq!(
-            Field::find_field_or_default("", "0:5").unwrap(),
+            Field::find_field_or_default("", "0:5", Dialect::Knuth).unwrap(),
             (
                 Field {
                     expression: "0:5".parse().unwrap()
@@ -112,7 +138,7 @@ mod tests {
                 0
             )
         );
-        assert!(Field::find_field_or_default("(%invalid%)", "0:5").is_err());
-        assert!(Field::find_field_or_default("", "0:").is_err());
+        assert!(Field::find_field_or_default("(%invalid%)", "0:5", Dialect::Knuth).is_err());
+        assert!(Field::find_field_or_default("", "0:", Dialect::Knuth).is_err());
     }
 }
@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Context, Result, bail};
+
+use crate::word::Word;
+
+use super::assemble::{BYTE_SIZE, N_WORDS};
+
+/// Marks the beginning of an image in the binary format
+const MAGIC: &[u8] = b"MIX\x01";
+
+/// Stands in for the start address in the binary format when there isn't one
+const NO_START: u16 = u16::MAX;
+
+/// A memory image: the contents of every word a program loads into memory, and
+/// the address execution starts from.
+///
+/// The text format has an optional `start <address>` line followed by one line per
+/// word giving its address, sign and five bytes, e.g. `3000 + 46 56 00 02 32`. A
+/// segment of consecutive identical words, like a BLOCK, is written as one line
+/// giving the first and last address, e.g. `0100-0199 + 00 00 00 00 00`.
+///
+/// The binary format is the magic bytes "MIX\x01" and the start address as a
+/// big-endian u16, followed by a record for each word: its address as a big-endian
+/// u16 and then its sign and five bytes, one u8 each.
+#[derive(Debug, Default, PartialEq)]
+pub struct Image {
+    pub words: BTreeMap<usize, Word>,
+    pub start: Option<usize>,
+}
+
+/// A run of consecutive addresses that all hold the same word
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub first: usize,
+    pub last: usize,
+    pub word: Word,
+}
+
+impl fmt::Display for Image {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(start) = self.start {
+            writeln!(f, "start {}", start)?;
+        }
+        for Segment { first, last, word } in self.segments() {
+            if first == last {
+                write!(f, "{:04}", first)?;
+            } else {
+                write!(f, "{:04}-{:04}", first, last)?;
+            }
+            writeln!(f, " {}", word)?;
+        }
+        Ok(())
+    }
+}
+
+impl Image {
+    /// The words of the image, with each run of identical words at consecutive
+    /// addresses merged into one segment
+    pub fn segments(&self) -> Vec<Segment> {
+        let mut segments: Vec<Segment> = Vec::new();
+        for (&address, &word) in &self.words {
+            match segments.last_mut() {
+                Some(segment) if segment.last + 1 == address && segment.word == word => {
+                    segment.last = address;
+                }
+                _ => segments.push(Segment {
+                    first: address,
+                    last: address,
+                    word,
+                }),
+            }
+        }
+        segments
+    }
+
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut data = MAGIC.to_vec();
+        let start = self.start.map(|s| s as u16).unwrap_or(NO_START);
+        data.extend(start.to_be_bytes());
+        for (&address, word) in &self.words {
+            data.extend((address as u16).to_be_bytes());
+            data.extend(word.to_bytes());
+        }
+        data
+    }
+
+    pub fn from_binary(data: &[u8]) -> Result<Self> {
+        let Some(data) = data.strip_prefix(MAGIC) else {
+            bail!("Not a binary MIX image");
+        };
+        if data.len() < 2 || (data.len() - 2) % 8 != 0 {
+            bail!("Binary MIX image is truncated");
+        }
+
+        let start = u16::from_be_bytes([data[0], data[1]]);
+        let mut image = Image {
+            words: BTreeMap::new(),
+            start: (start != NO_START).then_some(start as usize),
+        };
+        for record in data[2..].chunks(8) {
+            let address = u16::from_be_bytes([record[0], record[1]]) as usize;
+            let bytes: [u8; 6] = record[2..].try_into()?;
+            if address >= N_WORDS
+                || bytes[0] > 1
+                || bytes[1..].iter().any(|&b| b as i64 >= BYTE_SIZE)
+            {
+                bail!("Invalid word at address {} in binary MIX image", address);
+            }
+            image.words.insert(address, Word::from_bytes(bytes));
+        }
+        Ok(image)
+    }
+
+    /// Reads an image in either the text or binary format
+    pub fn load(data: &[u8]) -> Result<Self> {
+        if data.starts_with(MAGIC) {
+            Self::from_binary(data)
+        } else {
+            std::str::from_utf8(data)
+                .context("MIX image is neither binary nor text")?
+                .parse()
+        }
+    }
+
+    fn parse_record(&mut self, record: &str) -> Result<()> {
+        let fields: Vec<_> = record.split_whitespace().collect();
+        match fields[..] {
+            ["start", address] => self.start = Some(address.parse()?),
+            [addresses, sign, b1, b2, b3, b4, b5] => {
+                let (first, last) = match addresses.split_once('-') {
+                    Some((first, last)) => (first.parse()?, last.parse()?),
+                    None => (addresses.parse()?, addresses.parse()?),
+                };
+                if last >= N_WORDS {
+                    bail!("Address {} is outside of memory", last);
+                }
+                if first > last {
+                    bail!("Empty range of addresses: {}", addresses);
+                }
+                let mut bytes = [0; 6];
+                bytes[0] = match sign {
+                    "+" => 0,
+                    "-" => 1,
+                    _ => bail!("Invalid sign: {}", sign),
+                };
+                for (byte, s) in bytes[1..].iter_mut().zip([b1, b2, b3, b4, b5]) {
+                    *byte = s.parse()?;
+                    if *byte as i64 >= BYTE_SIZE {
+                        bail!("Byte {} is too large", byte);
+                    }
+                }
+                for address in first..=last {
+                    self.words.insert(address, Word::from_bytes(bytes));
+                }
+            }
+            _ => bail!("Unrecognized record: {}", record),
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Image {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut image = Image::default();
+        for (idx, record) in s.lines().enumerate().filter(|(_, r)| !r.trim().is_empty()) {
+            image
+                .parse_record(record)
+                .with_context(|| format!("Malformed image on line {}", idx + 1))?;
+        }
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let image = Image {
+            words: BTreeMap::from([
+                (0, Word::from_value(-5, BYTE_SIZE).unwrap()),
+                (3000, Word::from_value(12345, BYTE_SIZE).unwrap()),
+            ]),
+            start: Some(3000),
+        };
+        let text = image.to_string();
+        assert_eq!(
+            text,
+            "start 3000\n0000 - 00 00 00 00 05\n3000 + 00 00 03 00 57\n"
+        );
+        assert_eq!(text.parse::<Image>().unwrap(), image);
+    }
+
+    #[test]
+    fn test_segments() {
+        let zero = Word::ZERO;
+        let one = Word::from_value(1, BYTE_SIZE).unwrap();
+        let image = Image {
+            words: BTreeMap::from([(10, zero), (11, zero), (12, zero), (13, one), (15, one)]),
+            start: None,
+        };
+        assert_eq!(
+            image.segments(),
+            [
+                Segment {
+                    first: 10,
+                    last: 12,
+                    word: zero
+                },
+                Segment {
+                    first: 13,
+                    last: 13,
+                    word: one
+                },
+                Segment {
+                    first: 15,
+                    last: 15,
+                    word: one
+                },
+            ]
+        );
+        let text = image.to_string();
+        assert_eq!(
+            text,
+            "0010-0012 + 00 00 00 00 00\n0013 + 00 00 00 00 01\n0015 + 00 00 00 00 01\n"
+        );
+        assert_eq!(text.parse::<Image>().unwrap(), image);
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let image = Image {
+            words: BTreeMap::from([(1, Word::from_value(-64, BYTE_SIZE).unwrap())]),
+            start: None,
+        };
+        let data = image.to_binary();
+        assert_eq!(data, b"MIX\x01\xff\xff\x00\x01\x01\x00\x00\x00\x01\x00");
+        assert_eq!(Image::load(&data).unwrap(), image);
+        assert_eq!(Image::load(image.to_string().as_bytes()).unwrap(), image);
+
+        assert!(Image::from_binary(&data[..data.len() - 1]).is_err());
+        assert!(Image::from_binary(b"MIX\x01\x00\x00\x0f\xa0\x00\x00\x00\x00\x00\x00").is_err());
+    }
+
+    #[test]
+    fn test_from_str_errors() {
+        assert!("4000 + 00 00 00 00 00".parse::<Image>().is_err());
+        assert!("0 * 00 00 00 00 00".parse::<Image>().is_err());
+        assert!("0 + 00 00 00 00 64".parse::<Image>().is_err());
+        assert!("0 + 00 00 00 00".parse::<Image>().is_err());
+        assert!("start".parse::<Image>().is_err());
+        assert!("0010-0009 + 00 00 00 00 00".parse::<Image>().is_err());
+        assert!("3999-4000 + 00 00 00 00 00".parse::<Image>().is_err());
+    }
+}
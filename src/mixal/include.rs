@@ -0,0 +1,153 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use super::statement::Columns;
+
+/// The files currently being read, innermost last, used to catch INCLUDE cycles
+#[derive(Default)]
+pub struct IncludeStack {
+    /// Each file as (canonical path, name as given)
+    files: Vec<(PathBuf, String)>,
+}
+
+impl IncludeStack {
+    pub fn push(&mut self, file: &str) {
+        self.files
+            .push((canonical(Path::new(file)), file.to_string()));
+    }
+
+    pub fn pop(&mut self) {
+        self.files.pop();
+    }
+
+    /// Reads the file named by an `INCLUDE "file"` statement in `from`, returning
+    /// its name and text. Included paths are relative to the directory of the file
+    /// that includes them.
+    pub fn open(&self, from: &str, text: &str, columns: &Columns) -> Result<(String, String)> {
+        if columns.loc.is_some() {
+            bail!("INCLUDE cannot have a label");
+        }
+        let operand = columns.address.map_or("", |address| address.slice(text));
+        let name = operand
+            .strip_prefix('"')
+            .and_then(|s| s.strip_suffix('"'))
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Expected a quoted file name after INCLUDE, found '{}'",
+                    operand
+                )
+            })?;
+
+        let path = Path::new(from).parent().unwrap_or(Path::new("")).join(name);
+        let file = path.display().to_string();
+        let canonical = canonical(&path);
+        if let Some(pos) = self.files.iter().position(|(p, _)| *p == canonical) {
+            let cycle: Vec<&str> = self.files[pos..]
+                .iter()
+                .map(|(_, name)| name.as_str())
+                .chain([file.as_str()])
+                .collect();
+            bail!("INCLUDE cycle: {}", cycle.join(" -> "));
+        }
+
+        let text = fs::read_to_string(&path).with_context(|| format!("Failed to read {}", file))?;
+        Ok((file, text))
+    }
+}
+
+/// Identifies a file however it was named, falling back to the name itself for
+/// files that don't exist on disk, like "<stdin>"
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mixal::diagnostic::Sources;
+    use crate::mixal::reader::read;
+
+    #[test]
+    fn test_expand_include() {
+        let source = " INCLUDE \"include/units.mixal\"\n OUT 0(TAPE)\n";
+        let mut sources = Sources::default();
+        let (lines, errors) = read(
+            "test_data/main.mixal",
+            source,
+            false,
+            &mut sources,
+            &mut |_, _| true,
+        );
+        assert!(errors.is_empty());
+
+        let found: Vec<_> = lines
+            .iter()
+            .map(|l| (l.file.as_str(), l.number, l.directive))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("test_data/main.mixal", 1, true),
+                ("test_data/include/units.mixal", 1, false),
+                ("test_data/include/units.mixal", 2, false),
+                ("test_data/include/units.mixal", 3, false),
+                ("test_data/main.mixal", 2, false),
+            ]
+        );
+        assert_eq!(
+            sources.line("test_data/include/units.mixal", 2),
+            Some("TAPE       EQU  0")
+        );
+    }
+
+    #[test]
+    fn test_include_errors() {
+        let error = |source: &str| {
+            let mut stack = IncludeStack::default();
+            stack.push("test_data/main.mixal");
+            stack
+                .open("test_data/main.mixal", source, &Columns::of(source))
+                .map(|(file, _)| file)
+                .map_err(|e| format!("{:#}", e))
+        };
+        assert_eq!(
+            error(" INCLUDE \"include/units.mixal\""),
+            Ok("test_data/include/units.mixal".to_string())
+        );
+        assert!(
+            error(" INCLUDE \"include/missing.mixal\"")
+                .unwrap_err()
+                .starts_with("Failed to read")
+        );
+        assert!(
+            error(" INCLUDE include/units.mixal")
+                .unwrap_err()
+                .starts_with("Expected a quoted")
+        );
+        assert_eq!(
+            error("X INCLUDE \"include/units.mixal\""),
+            Err("INCLUDE cannot have a label".to_string())
+        );
+        assert_eq!(
+            error(" INCLUDE \"main.mixal\""),
+            Err("INCLUDE cycle: test_data/main.mixal -> test_data/main.mixal".to_string())
+        );
+
+        let (_, errors) = read(
+            "test_data/main.mixal",
+            " INCLUDE \"include/cycle_a.mixal\"",
+            false,
+            &mut Sources::default(),
+            &mut |_, _| true,
+        );
+        let errors: Vec<_> = errors.iter().map(|e| format!("{:#}", e.error)).collect();
+        assert_eq!(
+            errors,
+            ["INCLUDE cycle: test_data/include/cycle_a.mixal -> \
+                 test_data/include/cycle_b.mixal -> test_data/include/cycle_a.mixal"]
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use std::fmt;
+
+use crate::word::Word;
+
+use super::assemble::BYTE_SIZE;
+
+/// How to show a word in a listing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WordLayout {
+    /// As an instruction: ±AAAA I F C
+    Instruction,
+    /// As a sign and five bytes
+    Data,
+    /// As a sign and five character codes, followed by the characters they stand for
+    Text([char; 5]),
+}
+
+/// One line of an assembly listing
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListingLine {
+    /// The source line number, or None for constants placed after END and for the
+    /// words after the first of a TEXT
+    pub line: Option<usize>,
+    /// Whether the line was expanded from a macro, in which case the line number
+    /// is that of the macro body and is marked with a +
+    pub expanded: bool,
+    pub address: Option<usize>,
+    /// The last address of a block of identical words starting at `address`
+    pub last: Option<usize>,
+    pub word: Option<(Word, WordLayout)>,
+    pub source: String,
+}
+
+/// The source of a program side by side with the words it assembled to
+#[derive(Debug, Default, PartialEq)]
+pub struct Listing {
+    pub lines: Vec<ListingLine>,
+}
+
+fn format_word(word: &Word, layout: WordLayout) -> String {
+    let sign = if word.is_negative() { '-' } else { '+' };
+    let [a1, a2, i, f, c] = word.bytes;
+    match layout {
+        WordLayout::Instruction => format!(
+            "{} {:4} {:2} {:2} {:2}",
+            sign,
+            a1 as i64 * BYTE_SIZE + a2 as i64,
+            i,
+            f,
+            c
+        ),
+        WordLayout::Data | WordLayout::Text(_) => word.to_string(),
+    }
+}
+
+impl fmt::Display for ListingLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let line = self.line.map(|l| l.to_string()).unwrap_or_default();
+        let line = if self.expanded {
+            format!("+{}", line)
+        } else {
+            line
+        };
+        let address = self
+            .address
+            .map(|a| format!("{:04}", a))
+            .unwrap_or_default();
+        let word = self
+            .word
+            .map(|(word, layout)| format_word(&word, layout))
+            .unwrap_or_default();
+        let text = format!("{:>5}  {:4}  {:16}  {}", line, address, word, self.source);
+        write!(f, "{}", text.trim_end())?;
+        if let Some((_, WordLayout::Text(chars))) = self.word {
+            write!(f, "  \"{}\"", String::from_iter(chars))?;
+        }
+        if let (Some(first), Some(last)) = (self.address, self.last) {
+            write!(f, "  ({:04}-{:04})", first, last)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let stj = Word::from_bytes([0, 46, 63, 0, 2, 32]);
+        let lines = [
+            ListingLine {
+                line: Some(2),
+                expanded: false,
+                address: None,
+                last: None,
+                word: None,
+                source: "        ORIG 3000".to_string(),
+            },
+            ListingLine {
+                line: Some(3),
+                expanded: false,
+                address: Some(3000),
+                last: None,
+                word: Some((stj, WordLayout::Instruction)),
+                source: "MAXIMUM STJ  EXIT".to_string(),
+            },
+            ListingLine {
+                line: Some(7),
+                expanded: true,
+                address: Some(3004),
+                last: None,
+                word: Some((Word::from_value(1, BYTE_SIZE).unwrap(), WordLayout::Data)),
+                source: " CON  1".to_string(),
+            },
+            ListingLine {
+                line: Some(8),
+                expanded: false,
+                address: Some(3005),
+                last: None,
+                word: Some((
+                    Word::from_bytes([0, 8, 10, 0, 0, 0]),
+                    WordLayout::Text(['H', 'Δ', ' ', ' ', ' ']),
+                )),
+                source: "MSG ALF  HΔ".to_string(),
+            },
+            ListingLine {
+                line: Some(9),
+                expanded: false,
+                address: Some(3006),
+                last: Some(3007),
+                word: Some((Word::ZERO, WordLayout::Data)),
+                source: "BUF BLOCK 2".to_string(),
+            },
+            ListingLine {
+                line: None,
+                expanded: false,
+                address: Some(3008),
+                last: None,
+                word: Some((Word::from_value(-5, BYTE_SIZE).unwrap(), WordLayout::Data)),
+                source: " CON  -5".to_string(),
+            },
+        ];
+        let listing = Listing {
+            lines: lines.to_vec(),
+        };
+        let expected = [
+            "    2                                  ORIG 3000",
+            "    3  3000  + 3007  0  2 32   MAXIMUM STJ  EXIT",
+            "   +7  3004  + 00 00 00 00 01   CON  1",
+            "    8  3005  + 08 10 00 00 00  MSG ALF  HΔ  \"HΔ   \"",
+            "    9  3006  + 00 00 00 00 00  BUF BLOCK 2  (3006-3007)",
+            "       3008  - 00 00 00 00 05   CON  -5",
+        ];
+        assert_eq!(listing.to_string().lines().collect::<Vec<_>>(), expected);
+    }
+}
@@ -1,14 +1,24 @@
 mod alf;
 pub mod assemble;
+mod block;
 mod con;
+mod conditional;
+pub mod debug_info;
+pub mod diagnostic;
+pub mod disassemble;
 mod end;
 mod equ;
 mod expression;
 mod field;
+pub mod image;
+mod include;
 mod instruction;
+pub mod listing;
 mod number;
 mod operator;
 mod orig;
+mod reader;
 mod statement;
 mod symbol;
+mod text;
 mod wval;
@@ -1,6 +1,10 @@
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq)]
+use anyhow::Result;
+
+use crate::mixal::assemble::WORD_SIZE;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum UnaryOperator {
     Plus,
     Minus,
@@ -10,6 +14,13 @@ impl UnaryOperator {
     pub fn starts_with(s: &str) -> bool {
         s.starts_with(['+', '-'])
     }
+
+    pub fn apply(&self, value: i64) -> i64 {
+        match self {
+            UnaryOperator::Plus => value,
+            UnaryOperator::Minus => -value,
+        }
+    }
 }
 
 impl FromStr for UnaryOperator {
@@ -24,7 +35,7 @@ impl FromStr for UnaryOperator {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum BinaryOperator {
     Plus,
     Minus,
@@ -58,6 +69,26 @@ impl BinaryOperator {
             Some((pos, &s[pos..pos + 1]))
         }
     }
+
+    /// Applies the operator with the semantics given in TAOCP Vol. I, p. 154
+    pub fn apply(&self, left: i64, right: i64) -> Result<i64> {
+        match self {
+            BinaryOperator::Plus => Ok(left + right),
+            BinaryOperator::Minus => Ok(left - right),
+            BinaryOperator::Multiply => left
+                .checked_mul(right)
+                .ok_or_else(|| anyhow::anyhow!("Overflow multiplying {} by {}", left, right)),
+            BinaryOperator::IntDivide | BinaryOperator::ScaledDivide if right == 0 => {
+                anyhow::bail!("Division by zero")
+            }
+            BinaryOperator::IntDivide => Ok(left / right),
+            // a//b divides a shifted left by a full word, so the product needs the extra width
+            BinaryOperator::ScaledDivide => {
+                Ok((left as i128 * WORD_SIZE as i128 / right as i128) as i64)
+            }
+            BinaryOperator::Colon => Ok(8 * left + right),
+        }
+    }
 }
 
 impl FromStr for BinaryOperator {
@@ -81,6 +112,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_unop_starts_with() {
         assert_eq!(UnaryOperator::starts_with("+37"), true);
         assert_eq!(UnaryOperator::starts_with("-37"), true);
@@ -0,0 +1,634 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::rc::Rc;
+use std::str::Chars;
+
+use anyhow::{Result, anyhow, bail};
+
+use super::diagnostic::{Code, Sources, Span};
+use super::include::IncludeStack;
+use super::statement::{Columns, Operation};
+use super::symbol::Symbol;
+
+/// How deeply macro calls can nest before a macro is assumed to call itself forever
+const MAX_EXPANSION_DEPTH: usize = 64;
+
+/// How many macro calls a program can expand. Local labels are numbered by call,
+/// and with at most four digits a local label like 9999MLOOP fits in a symbol.
+const MAX_EXPANSIONS: usize = 9999;
+
+/// The longest name a local label %NAME can have
+const MAX_LOCAL_LABEL: usize = 5;
+
+/// A line of MIXAL source, from whichever file it was read
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub file: String,
+    /// The line number within the file, counting from 1
+    pub number: usize,
+    /// The line as written in the file
+    pub text: String,
+    /// For lines produced by a macro, the text after substituting its arguments
+    /// and local labels
+    pub expanded: Option<String>,
+    /// The macro call that produced the line, if any
+    pub expansion: Option<Rc<Expansion>>,
+    /// Whether the line was handled while reading, like INCLUDE or a macro
+    /// definition, rather than being a statement to assemble
+    pub directive: bool,
+}
+
+impl Line {
+    /// The text to assemble
+    pub fn statement(&self) -> &str {
+        self.expanded.as_deref().unwrap_or(&self.text)
+    }
+}
+
+/// A call of a macro, which the lines of its body were expanded from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expansion {
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+    pub span: Option<Span>,
+    /// The call this one was expanded from, for macros called by other macros
+    pub parent: Option<Rc<Expansion>>,
+}
+
+/// A line that couldn't be read, like an INCLUDE of a missing file
+#[derive(Debug)]
+pub struct ReadError {
+    pub code: Code,
+    pub file: String,
+    pub line: usize,
+    pub span: Option<Span>,
+    /// Where the error falls among the lines read
+    pub index: usize,
+    pub expansion: Option<Rc<Expansion>>,
+    pub error: anyhow::Error,
+}
+
+/// A macro defined with MACRO and ENDM
+struct Macro {
+    /// The lines between MACRO and ENDM
+    body: Vec<Line>,
+}
+
+/// Reads a program, following each `INCLUDE "file"` with the lines of that file.
+/// Included paths are relative to the directory of the file that includes them.
+/// If `macros` is set, macros are defined with MACRO and ENDM and expanded where
+/// they are called.
+///
+/// Every line that isn't handled while reading is passed to `assemble` as soon as
+/// it is read, along with its index among the lines. It returns whether the lines
+/// that follow are assembled; where they aren't, like inside an IF that isn't
+/// taken, INCLUDE, MACRO and macro calls are skipped.
+pub fn read(
+    file: &str,
+    text: &str,
+    macros: bool,
+    sources: &mut Sources,
+    assemble: &mut dyn FnMut(usize, &Line) -> bool,
+) -> (Vec<Line>, Vec<ReadError>) {
+    let mut reader = Reader {
+        stack: IncludeStack::default(),
+        lines: Vec::new(),
+        errors: Vec::new(),
+        macros_enabled: macros,
+        macros: HashMap::new(),
+        defining: None,
+        expansions: 0,
+        assemble,
+        active: true,
+    };
+    reader.read_file(file, text, sources);
+    (reader.lines, reader.errors)
+}
+
+struct Reader<'a> {
+    stack: IncludeStack,
+    lines: Vec<Line>,
+    errors: Vec<ReadError>,
+    macros_enabled: bool,
+    macros: HashMap<String, Macro>,
+    /// The macro whose body is being read, with the line of its MACRO statement.
+    /// The name is None if the MACRO statement was invalid, in which case the body
+    /// is skipped.
+    defining: Option<(Option<String>, Line, Macro)>,
+    /// How many macro calls have been expanded, used to make local labels unique
+    expansions: usize,
+    assemble: &'a mut dyn FnMut(usize, &Line) -> bool,
+    /// Whether the lines being read are assembled
+    active: bool,
+}
+
+impl Reader<'_> {
+    fn read_file(&mut self, file: &str, text: &str, sources: &mut Sources) {
+        sources.add(file, text);
+        self.stack.push(file);
+
+        for (idx, text) in text.lines().enumerate() {
+            let line = Line {
+                file: file.to_string(),
+                number: idx + 1,
+                text: text.to_string(),
+                expanded: None,
+                expansion: None,
+                directive: false,
+            };
+            self.read_line(line, sources);
+        }
+
+        // A macro has to end in the file it started in
+        if let Some((name, start, _)) = self.defining.take_if(|(_, start, _)| start.file == file) {
+            let span = Columns::of(&start.text).op;
+            let name = name.map(|name| format!(" {}", name)).unwrap_or_default();
+            let error = anyhow!("MACRO{} has no matching ENDM", name);
+            self.error(Code::Macro, &start, span, error);
+        }
+        self.stack.pop();
+    }
+
+    fn read_line(&mut self, mut line: Line, sources: &mut Sources) {
+        let text = line.statement().to_string();
+        let columns = Columns::of(&text);
+        let op = columns
+            .op
+            .filter(|_| !text.starts_with('*'))
+            .map(|op| op.slice(&text));
+
+        // Every line up to ENDM is part of the macro being defined
+        if self.defining.is_some() {
+            line.directive = true;
+            self.lines.push(line.clone());
+            match op {
+                Some("ENDM") => {
+                    if let Some((Some(name), _, definition)) = self.defining.take() {
+                        self.macros.insert(name, definition);
+                    }
+                }
+                Some("MACRO") => {
+                    let error = anyhow!("A macro cannot be defined inside another macro");
+                    self.error(Code::Macro, &line, columns.op, error);
+                }
+                _ => match check_local_labels(&text) {
+                    Ok(()) => {
+                        if let Some((_, _, definition)) = &mut self.defining {
+                            definition.body.push(line);
+                        }
+                    }
+                    Err((span, error)) => self.error(Code::Macro, &line, Some(span), error),
+                },
+            }
+            return;
+        }
+
+        // Directives in lines that aren't assembled are skipped, along with the body
+        // of a macro definition
+        if !self.active && matches!(op, Some(name) if self.is_directive(name)) {
+            line.directive = true;
+            if op == Some("MACRO") && self.macros_enabled {
+                self.defining = Some((None, line.clone(), Macro { body: Vec::new() }));
+            }
+            self.lines.push(line);
+            return;
+        }
+
+        let result = match op {
+            Some("INCLUDE") => {
+                line.directive = true;
+                self.lines.push(line.clone());
+                self.include(&line, &text, &columns, sources)
+                    .map_err(|err| (Code::Include, err))
+            }
+            Some(op @ ("MACRO" | "ENDM")) => {
+                line.directive = true;
+                self.lines.push(line.clone());
+                self.define(op, &text, &columns, &line)
+                    .map_err(|err| (Code::Macro, err))
+            }
+            Some(name) if self.macros.contains_key(name) => {
+                line.directive = true;
+                self.lines.push(line.clone());
+                self.expand(name, &text, &columns, &line, sources)
+                    .map_err(|err| (Code::Macro, err))
+            }
+            _ => {
+                self.lines.push(line);
+                let idx = self.lines.len() - 1;
+                self.active = (self.assemble)(idx, &self.lines[idx]);
+                return;
+            }
+        };
+        if let Err((code, error)) = result {
+            self.error(code, &line, columns.address.or(columns.op), error);
+        }
+    }
+
+    /// Whether an operation is handled while reading rather than assembled
+    fn is_directive(&self, op: &str) -> bool {
+        matches!(op, "INCLUDE" | "MACRO" | "ENDM") || self.macros.contains_key(op)
+    }
+
+    fn error(&mut self, code: Code, line: &Line, span: Option<Span>, error: anyhow::Error) {
+        self.errors.push(ReadError {
+            code,
+            file: line.file.clone(),
+            line: line.number,
+            span,
+            index: self.lines.len().saturating_sub(1),
+            expansion: line.expansion.clone(),
+            error,
+        });
+    }
+
+    fn include(
+        &mut self,
+        from: &Line,
+        text: &str,
+        columns: &Columns,
+        sources: &mut Sources,
+    ) -> Result<()> {
+        let (file, text) = self.stack.open(&from.file, text, columns)?;
+        self.read_file(&file, &text, sources);
+        Ok(())
+    }
+
+    /// Starts the definition of a macro, named in the LOC field of its MACRO statement
+    fn define(&mut self, op: &str, text: &str, columns: &Columns, line: &Line) -> Result<()> {
+        if !self.macros_enabled {
+            bail!("{} is only allowed when macros are enabled", op);
+        }
+        if op == "ENDM" {
+            bail!("ENDM without a MACRO");
+        }
+        // Skip the body up to ENDM even if the MACRO statement is invalid
+        self.defining = Some((None, line.clone(), Macro { body: Vec::new() }));
+        let Some(loc) = columns.loc else {
+            bail!("MACRO needs a name in the LOC field");
+        };
+        let name = Symbol::new(loc.slice(text))?.0;
+        if Operation::is_opcode(&name) || matches!(name.as_str(), "INCLUDE" | "MACRO" | "ENDM") {
+            bail!("Macro {} would hide the operation of the same name", name);
+        }
+        if let Some(address) = columns.address {
+            bail!(
+                "MACRO takes no operand, found '{}'. Parameters are referred to as &1 to &9",
+                address.slice(text)
+            );
+        }
+        if let Some((defining, _, _)) = &mut self.defining {
+            *defining = Some(name);
+        }
+        Ok(())
+    }
+
+    /// Reads the body of a macro in place of a call to it
+    fn expand(
+        &mut self,
+        name: &str,
+        text: &str,
+        columns: &Columns,
+        call: &Line,
+        sources: &mut Sources,
+    ) -> Result<()> {
+        let mut depth = 0;
+        let mut parent = call.expansion.as_deref();
+        while let Some(expansion) = parent {
+            depth += 1;
+            parent = expansion.parent.as_deref();
+        }
+        if depth >= MAX_EXPANSION_DEPTH {
+            bail!(
+                "Macro {} is nested more than {} deep",
+                name,
+                MAX_EXPANSION_DEPTH
+            );
+        }
+
+        if self.expansions >= MAX_EXPANSIONS {
+            bail!("More than {} macro calls", MAX_EXPANSIONS);
+        }
+        self.expansions += 1;
+        let unique = self.expansions;
+        let arguments = columns
+            .address
+            .map(|address| split_arguments(address.slice(text)))
+            .unwrap_or_default();
+        let expansion = Rc::new(Expansion {
+            name: name.to_string(),
+            file: call.file.clone(),
+            line: call.number,
+            span: columns.op,
+            parent: call.expansion.clone(),
+        });
+
+        // A label on the call refers to the first word of the expansion
+        if let Some(loc) = columns.loc {
+            let label = Line {
+                expanded: Some(format!("{} EQU *", loc.slice(text))),
+                expansion: Some(expansion.clone()),
+                directive: false,
+                ..call.clone()
+            };
+            self.read_line(label, sources);
+        }
+
+        let body: Vec<Line> = self.macros[name].body.to_vec();
+        for line in body {
+            let line = Line {
+                expansion: Some(expansion.clone()),
+                directive: false,
+                ..line
+            };
+            match substitute(line.statement(), &arguments, unique) {
+                Ok(expanded) => {
+                    let line = Line {
+                        expanded: Some(expanded),
+                        ..line
+                    };
+                    self.read_line(line, sources)
+                }
+                Err(error) => {
+                    let span = Columns::of(&line.text).address;
+                    self.error(
+                        Code::Macro,
+                        &line,
+                        span,
+                        error.context(format!("In macro {}", name)),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits the operand of a macro call into arguments at the commas that aren't
+/// inside parentheses
+fn split_arguments(operand: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in operand.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                arguments.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    arguments.push(current);
+    arguments
+}
+
+/// The fields of a line of a macro body that parameters and local labels are
+/// substituted in: LOC, OP and ADDRESS. The address ends at its first blank, so
+/// remarks are left alone, and so is the character data of ALF.
+fn macro_fields(text: &str) -> Vec<Span> {
+    if text.starts_with('*') {
+        return Vec::new();
+    }
+    let columns = Columns::of(text);
+    let alf = columns
+        .op
+        .is_some_and(|op| op.slice(text).eq_ignore_ascii_case("ALF"));
+    let address = columns.address.filter(|_| !alf).map(|address| {
+        let field = address.slice(text);
+        let end = field.find(char::is_whitespace).unwrap_or(field.len());
+        Span {
+            end: address.start + field[..end].chars().count(),
+            ..address
+        }
+    });
+    [columns.loc, columns.op, address]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Reads the name of a local label after its '%'
+fn local_label(chars: &mut Peekable<Chars>) -> Result<String> {
+    let mut name = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_alphanumeric) {
+        name.push(c);
+    }
+    if name.is_empty() {
+        bail!("Expected a local label name after '%'");
+    }
+    if name.len() > MAX_LOCAL_LABEL {
+        bail!(
+            "Local label %{} is longer than {} characters",
+            name,
+            MAX_LOCAL_LABEL
+        );
+    }
+    Ok(name)
+}
+
+/// Checks the local labels of a line of a macro body where the macro is defined,
+/// saying which field holds one that is invalid
+fn check_local_labels(text: &str) -> Result<(), (Span, anyhow::Error)> {
+    for span in macro_fields(text) {
+        let mut chars = span.slice(text).chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '%' {
+                local_label(&mut chars).map_err(|error| (span, error))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the parameters &1 to &9 in a line of a macro body with the arguments
+/// of a call, and each local label %NAME with a name unique to the call, like 3MNAME
+fn substitute(text: &str, arguments: &[String], unique: usize) -> Result<String> {
+    let mut expanded = String::new();
+    let mut copied = 0;
+    for span in macro_fields(text) {
+        let range = span.bytes(text);
+        expanded.push_str(&text[copied..range.start]);
+        copied = range.end;
+
+        let mut chars = text[range].chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '&' => {
+                    let digit = chars
+                        .next_if(|c| c.is_ascii_digit() && *c != '0')
+                        .ok_or_else(|| anyhow!("Expected a parameter from &1 to &9 after '&'"))?;
+                    let idx = digit.to_digit(10).unwrap() as usize;
+                    let argument = arguments.get(idx - 1).ok_or_else(|| {
+                        anyhow!(
+                            "Parameter &{} has no argument, only {} were given",
+                            idx,
+                            arguments.len()
+                        )
+                    })?;
+                    expanded.push_str(argument);
+                }
+                '%' => {
+                    let name = local_label(&mut chars)?;
+                    expanded.push_str(&format!("{}M{}", unique, name));
+                }
+                _ => expanded.push(c),
+            }
+        }
+    }
+    expanded.push_str(&text[copied..]);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_source(source: &str, macros: bool) -> (Vec<Line>, Vec<String>) {
+        let (lines, errors) = read(
+            "test_data/main.mixal",
+            source,
+            macros,
+            &mut Sources::default(),
+            &mut |_, _| true,
+        );
+        let errors = errors
+            .into_iter()
+            .map(|e| format!("{:#}", e.error))
+            .collect();
+        (lines, errors)
+    }
+
+    #[test]
+    fn test_expand_macro() {
+        let source = "\
+SAVE MACRO
+%L ST&1 &2
+ JMP %L
+ ENDM
+HERE SAVE 1,TMP(0:2)
+ SAVE 2,TMP";
+        let (lines, errors) = read_source(source, true);
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let statements: Vec<_> = lines
+            .iter()
+            .filter(|l| !l.directive)
+            .map(|l| (l.statement(), l.number))
+            .collect();
+        assert_eq!(
+            statements,
+            [
+                ("HERE EQU *", 5),
+                ("1ML ST1 TMP(0:2)", 2),
+                (" JMP 1ML", 3),
+                ("2ML ST2 TMP", 2),
+                (" JMP 2ML", 3),
+            ]
+        );
+        let expansion = lines[5].expansion.as_ref().unwrap();
+        assert_eq!((expansion.name.as_str(), expansion.line), ("SAVE", 5));
+    }
+
+    #[test]
+    fn test_nested_macros() {
+        let source = "\
+INNER MACRO
+ NOP &1
+ ENDM
+OUTER MACRO
+ INNER &1
+ ENDM
+ OUTER 7";
+        let (lines, errors) = read_source(source, true);
+        assert!(errors.is_empty(), "{:?}", errors);
+        let nop = lines.iter().find(|l| !l.directive).unwrap();
+        assert_eq!(nop.statement(), " NOP 7");
+        let expansion = nop.expansion.as_ref().unwrap();
+        assert_eq!(expansion.name, "INNER");
+        assert_eq!(expansion.parent.as_ref().unwrap().name, "OUTER");
+    }
+
+    #[test]
+    fn test_macro_errors() {
+        let error = |source| read_source(source, true).1;
+        assert_eq!(
+            read_source("M MACRO\n ENDM", false).1,
+            [
+                "MACRO is only allowed when macros are enabled",
+                "ENDM is only allowed when macros are enabled"
+            ]
+        );
+        assert_eq!(error("M MACRO\n NOP"), ["MACRO M has no matching ENDM"]);
+        assert_eq!(error(" ENDM"), ["ENDM without a MACRO"]);
+        assert_eq!(
+            error("LDA MACRO\n ENDM"),
+            ["Macro LDA would hide the operation of the same name"]
+        );
+        assert_eq!(
+            error(" MACRO\n LDA 1\n ENDM"),
+            ["MACRO needs a name in the LOC field"]
+        );
+        assert_eq!(
+            error(" MACRO"),
+            [
+                "MACRO needs a name in the LOC field",
+                "MACRO has no matching ENDM"
+            ]
+        );
+        assert_eq!(
+            error("M MACRO\n NOP &2\n ENDM\n M 1"),
+            ["In macro M: Parameter &2 has no argument, only 1 were given"]
+        );
+        assert_eq!(
+            error("M MACRO\n M\n ENDM\n M"),
+            ["Macro M is nested more than 64 deep"]
+        );
+    }
+
+    #[test]
+    fn test_substitute_fields() {
+        let arguments = ["100".to_string()];
+        let substitute = |text| substitute(text, &arguments, 12).unwrap();
+        assert_eq!(substitute("%L LDA &1"), "12ML LDA 100");
+        // Remarks and ALF character data aren't fields
+        assert_eq!(
+            substitute(" LDA &1 load 100% of it & more"),
+            " LDA 100 load 100% of it & more"
+        );
+        assert_eq!(substitute(" ALF &1%AB"), " ALF &1%AB");
+        assert_eq!(substitute("* &1 is 100%"), "* &1 is 100%");
+
+        let (_, errors) = read_source("M MACRO\n LDA &1 load 100% of it\n ENDM\n M 100", true);
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_local_label_length() {
+        // A name of five characters fits in a symbol with the number of any call
+        let mut source = "M MACRO\n%LOOPS JMP %LOOPS\n ENDM\n".to_string();
+        source.push_str(&" M\n".repeat(MAX_EXPANSIONS + 1));
+        let (lines, errors) = read_source(&source, true);
+        assert_eq!(errors, ["More than 9999 macro calls"]);
+        let last = lines.iter().rfind(|l| !l.directive).unwrap();
+        assert_eq!(last.statement(), "9999MLOOPS JMP 9999MLOOPS");
+        assert!(Symbol::new("9999MLOOPS").is_ok());
+
+        let (lines, errors) = read_source("M MACRO\n JMP %LOOPSTART\n ENDM\n M", true);
+        assert_eq!(
+            errors,
+            ["Local label %LOOPSTART is longer than 5 characters"]
+        );
+        assert!(lines.iter().all(|l| l.directive));
+    }
+
+    #[test]
+    fn test_split_arguments() {
+        assert_eq!(split_arguments("A,B(1:2),=3="), ["A", "B(1:2)", "=3="]);
+        assert_eq!(split_arguments("X,"), ["X", ""]);
+    }
+}
@@ -1,14 +1,21 @@
 use std::str::FromStr;
 
-use anyhow::Result;
+use anyhow::{Result, anyhow, bail};
 
 use super::alf::Alf;
+use super::assemble::Dialect;
+use super::block::Block;
 use super::con::Con;
+use super::conditional::If;
+use super::diagnostic::Span;
 use super::end::End;
 use super::equ::Equ;
-use super::instruction::MixInstruction;
+use super::expression::Expression;
+use super::instruction::{MixInstruction, OPCODES};
 use super::orig::Orig;
 use super::symbol::Symbol;
+use super::text::Text;
+use super::wval::WVal;
 
 pub enum Operation {
     Instruction(MixInstruction),
@@ -16,69 +23,338 @@ pub enum Operation {
     Orig(Orig),
     Con(Con),
     Alf(Alf),
+    Text(Text),
+    Block(Block),
     End(End),
+    If(If),
+    Else,
+    EndIf,
 }
 
-impl FromStr for Operation {
-    type Err = anyhow::Error;
+impl Operation {
+    /// Whether `opcode` is a pseudo-operation or a machine instruction mnemonic
+    pub fn is_opcode(opcode: &str) -> bool {
+        matches!(
+            opcode,
+            "EQU" | "ORIG" | "CON" | "ALF" | "TEXT" | "BLOCK" | "END" | "IF" | "ELSE" | "ENDIF"
+        ) || OPCODES.iter().any(|(mnemonic, _, _)| *mnemonic == opcode)
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Operation {
+    /// Parses the OP and ADDRESS fields of a line written in `dialect`
+    pub fn parse(s: &str, dialect: Dialect) -> Result<Self> {
         // Some operations need an operand, some can just be an opcode
         // We should just pass the remainder, whether it is an empty string or contains the operand,
         // and let the constructor handle the empty string case
         let (opcode, rest) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let opcode = match dialect {
+            Dialect::Knuth => opcode.to_string(),
+            Dialect::Mdk => opcode.to_ascii_uppercase(),
+        };
+        let wval = |s: &str| WVal::parse(s.trim(), dialect);
 
-        match opcode {
-            "EQU" => Ok(Operation::Equ(Equ {
-                wval: rest.trim().parse()?,
-            })),
-            "ORIG" => Ok(Operation::Orig(Orig {
-                wval: rest.trim().parse()?,
-            })),
-            "CON" => Ok(Operation::Con(Con {
-                wval: rest.trim().parse()?,
-            })),
-            "ALF" => Ok(Operation::Alf(Alf::from_char_data(rest)?)),
-            "END" => Ok(Operation::End(End {
-                wval: rest.trim().parse()?,
+        match opcode.as_str() {
+            "EQU" => Ok(Operation::Equ(Equ { wval: wval(rest)? })),
+            "ORIG" => Ok(Operation::Orig(Orig { wval: wval(rest)? })),
+            "CON" => Ok(Operation::Con(Con { wval: wval(rest)? })),
+            "ALF" => Ok(Operation::Alf(Alf::parse(rest, dialect)?)),
+            "TEXT" => Ok(Operation::Text(Text::parse(rest, dialect)?)),
+            "BLOCK" => Ok(Operation::Block(Block::parse(rest, dialect)?)),
+            "END" => Ok(Operation::End(End { wval: wval(rest)? })),
+            "IF" => Ok(Operation::If(If {
+                condition: Expression::parse(rest.trim(), dialect)?,
             })),
+            "ELSE" | "ENDIF" if !rest.trim().is_empty() => {
+                bail!("{} takes no operand, found '{}'", opcode, rest.trim())
+            }
+            "ELSE" => Ok(Operation::Else),
+            "ENDIF" => Ok(Operation::EndIf),
             _ => Ok(Operation::Instruction(MixInstruction::try_parse(
-                opcode,
+                &opcode,
                 rest.trim(),
+                dialect,
             )?)),
         }
     }
 }
 
+impl FromStr for Operation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Operation::parse(s, Dialect::Knuth)
+    }
+}
+
+/// The part of a line that `dialect` assembles. GNU MDK ends a line at a '#' outside
+/// of a quoted string or unquoted ALF data, or at the first blank after the address;
+/// historical MIXAL keeps the whole line.
+pub fn code(line: &str, dialect: Dialect) -> &str {
+    if dialect == Dialect::Knuth {
+        return line;
+    }
+
+    let address_field = if line.starts_with(char::is_whitespace) {
+        2
+    } else {
+        3
+    };
+    let mut fields = 0;
+    let mut op = "";
+    let mut start = 0;
+    let mut in_field = false;
+    let mut quoted = false;
+    let mut alf_data = false;
+    for (idx, c) in line.char_indices() {
+        if c.is_whitespace() && !quoted {
+            if in_field && fields == address_field - 1 {
+                op = &line[start..idx];
+            }
+            // Unquoted ALF data may contain blanks, so it can't have a remark
+            if in_field && fields == address_field && !alf_data {
+                return &line[..idx];
+            }
+            in_field = false;
+        } else if !in_field {
+            in_field = true;
+            fields += 1;
+            start = idx;
+            // Unquoted ALF data runs to the end of the line, blanks and '#' included
+            alf_data |= fields == address_field && op.eq_ignore_ascii_case("ALF") && c != '"';
+        }
+        if c == '#' && !quoted && !alf_data {
+            return line[..idx].trim_end();
+        }
+        if c == '"' {
+            quoted = !quoted;
+        }
+    }
+    line
+}
+
+/// Where the fields of a statement sit on its line
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Columns {
+    pub loc: Option<Span>,
+    pub op: Option<Span>,
+    pub address: Option<Span>,
+}
+
+impl Columns {
+    /// Splits a line into fields the same way Statement does
+    pub fn of(line: &str) -> Self {
+        let mut columns = Columns::default();
+        let has_loc = !line.starts_with(char::is_whitespace);
+        // Each field is a run of non-whitespace, except the address which runs to the end
+        let terminated = if has_loc { 2 } else { 1 };
+        let mut fields = Vec::new();
+        let mut start = None;
+        for (idx, c) in line.char_indices() {
+            match (start, c.is_whitespace()) {
+                (None, false) => start = Some(idx),
+                (Some(s), true) if fields.len() < terminated => {
+                    fields.push(Span::from_bytes(line, s, idx));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            let end = s + line[s..].trim_end().len();
+            fields.push(Span::from_bytes(line, s, end));
+        }
+
+        let mut fields = fields.into_iter();
+        if has_loc {
+            columns.loc = fields.next();
+        }
+        columns.op = fields.next();
+        columns.address = fields.next();
+        columns
+    }
+}
+
+impl Columns {
+    /// Moves a span to the same field of another line, for pointing at a line of a
+    /// macro body when the error was found in its expansion
+    pub fn remap(&self, span: Span, to: &Columns) -> Option<Span> {
+        let within = |field: Option<Span>| {
+            field.is_some_and(|field| field.start <= span.start && span.end <= field.end)
+        };
+        if self == to {
+            Some(span)
+        } else if within(self.loc) {
+            to.loc
+        } else if within(self.op) {
+            to.op
+        } else {
+            to.address
+        }
+    }
+}
+
+/// A line that couldn't be parsed, and the columns of the field at fault
+#[derive(Debug)]
+pub struct ParseError {
+    pub span: Option<Span>,
+    pub error: anyhow::Error,
+}
+
 /// Corresponds to one line of input in a MIXAL program
 pub struct Statement {
-    loc: Option<Symbol>,
-    op: Operation,
+    pub loc: Option<Symbol>,
+    pub op: Operation,
 }
 
-impl FromStr for Statement {
-    type Err = anyhow::Error;
+impl Statement {
+    /// Parses a line like `from_str`, but also says which field an error is in
+    pub fn parse(s: &str, dialect: Dialect) -> Result<Self, ParseError> {
+        let columns = Columns::of(s);
+        let at = |span: Option<Span>| move |error: anyhow::Error| ParseError { span, error };
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
         // Each line of a Mixal program can contain an optional LOC field, which is
         // determined by whether or not the line begins with whitespace (thus skipping
         // the LOC field) or not
         let (loc, opstr) = if !s.starts_with(char::is_whitespace) {
             if let Some((before, after)) = s.split_once(char::is_whitespace) {
-                (Some(before.parse()?), after.trim_start())
+                (
+                    Some(Symbol::parse(before, dialect).map_err(at(columns.loc))?),
+                    after.trim_start(),
+                )
             } else {
                 // If we get here, we had a character in the symbol field but no corresponding OP
                 // field, which is an invalid statement
-                anyhow::bail!("Missing OP field after LOC field in: {}", s);
+                return Err(at(columns.loc)(anyhow!(
+                    "Missing OP field after LOC field in: {}",
+                    s
+                )));
             }
         } else {
             // No LOC field, so proceed with parsing the entire s (after leading whitspace) as an OP field
             (None, s.trim_start())
         };
 
+        // Blame the operand unless the opcode itself is unknown
+        let opcode = opstr.split_whitespace().next().unwrap_or("");
+        let opcode = match dialect {
+            Dialect::Knuth => opcode.to_string(),
+            Dialect::Mdk => opcode.to_ascii_uppercase(),
+        };
+        let span = if Operation::is_opcode(&opcode) {
+            columns.address.or(columns.op)
+        } else {
+            columns.op
+        };
         Ok(Statement {
-            loc: loc,
-            op: opstr.parse()?,
+            loc,
+            op: Operation::parse(opstr, dialect).map_err(at(span))?,
         })
     }
 }
+
+impl FromStr for Statement {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Statement::parse(s, Dialect::Knuth).map_err(|e| e.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(start: usize, end: usize) -> Option<Span> {
+        Some(Span { start, end })
+    }
+
+    #[test]
+    fn test_columns() {
+        assert_eq!(
+            Columns::of("LOOP LDA  X,1(0:3) "),
+            Columns {
+                loc: span(1, 5),
+                op: span(6, 9),
+                address: span(11, 19),
+            }
+        );
+        assert_eq!(
+            Columns::of(" ALF AB CD"),
+            Columns {
+                loc: None,
+                op: span(2, 5),
+                address: span(6, 11),
+            }
+        );
+        assert_eq!(
+            Columns::of(" HLT"),
+            Columns {
+                loc: None,
+                op: span(2, 5),
+                address: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_columns_remap() {
+        let expanded = Columns::of("1ML ST1 TMP(0:2)");
+        let written = Columns::of("%L ST&1 &2");
+        assert_eq!(
+            expanded.remap(Span { start: 1, end: 4 }, &written),
+            span(1, 3)
+        );
+        assert_eq!(
+            expanded.remap(Span { start: 9, end: 12 }, &written),
+            span(9, 11)
+        );
+        assert_eq!(
+            written.remap(Span { start: 4, end: 6 }, &written),
+            span(4, 6)
+        );
+    }
+
+    #[test]
+    fn test_parse_error_span() {
+        let error_span = |s: &str| Statement::parse(s, Dialect::Knuth).err().unwrap().span;
+        assert_eq!(error_span("abc LDA 1"), span(1, 4));
+        assert_eq!(error_span("X BOGUS 1"), span(3, 8));
+        assert_eq!(error_span(" LDA 1,,2"), span(6, 10));
+        assert_eq!(error_span("LONELY"), span(1, 7));
+    }
+
+    #[test]
+    fn test_code() {
+        let mdk = |s| code(s, Dialect::Mdk);
+        assert_eq!(mdk("LOOP lda X,1 load the next value"), "LOOP lda X,1");
+        assert_eq!(mdk(" hlt"), " hlt");
+        assert_eq!(mdk(" ENT1 0   # start at zero"), " ENT1 0");
+        assert_eq!(mdk("# a comment"), "");
+        assert_eq!(mdk(" ALF \"A #B\" remark"), " ALF \"A #B\"");
+        assert_eq!(mdk(" ALF  HE LO"), " ALF  HE LO");
+        assert_eq!(mdk(" ALF  A#BCD"), " ALF  A#BCD");
+        assert_eq!(mdk(" ALF  A B#D"), " ALF  A B#D");
+        assert_eq!(mdk(" ALF \"A\" # remark"), " ALF \"A\"");
+        assert_eq!(
+            code(" LDA X # not a comment", Dialect::Knuth),
+            " LDA X # not a comment"
+        );
+    }
+
+    #[test]
+    fn test_parse_mdk() {
+        let parse = |s: &str| Statement::parse(s, Dialect::Mdk);
+        let statement = parse("very_long_label lda   counter(1:3)").unwrap();
+        assert_eq!(statement.loc, Some(Symbol("very_long_label".to_string())));
+        assert!(matches!(statement.op, Operation::Instruction(_)));
+        assert!(matches!(
+            parse(" alf \"HI\"").unwrap().op,
+            Operation::Alf(_)
+        ));
+        assert!(matches!(parse(" Equ 5").unwrap().op, Operation::Equ(_)));
+
+        assert!(Statement::parse(" lda 1", Dialect::Knuth).is_err());
+        assert_eq!(parse(" bogus 1").err().unwrap().span, span(2, 7));
+    }
+}
@@ -2,21 +2,28 @@ use std::str::FromStr;
 
 use anyhow::{Result, anyhow, bail};
 
+use super::assemble::Dialect;
+
 /// A Symbol represents a string of characters in the MIXAL assembly language that can
 /// "stand for" a raw numerical value. These will be replaced with the underlying values
 /// when assembled into MIX machine code.
 /// The parsing rules are as laid out in TAOCP Vol. I, p. 153
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Symbol(pub String);
 impl Symbol {
     pub const MAX_LENGTH: usize = 10;
     pub fn new(s: &str) -> Result<Self, anyhow::Error> {
+        Self::parse(s, Dialect::Knuth)
+    }
+
+    /// Parses a symbol, allowing the longer, lowercase names of GNU MDK in that dialect
+    pub fn parse(s: &str, dialect: Dialect) -> Result<Self, anyhow::Error> {
         // TODO: It may make sense to create a proper error type, but using anyhow for now
         if s.is_empty() {
             return Err(anyhow!("Cannot construct symbol from empty string"));
         }
 
-        if s.len() > Self::MAX_LENGTH {
+        if dialect == Dialect::Knuth && s.len() > Self::MAX_LENGTH {
             bail!(
                 "Symbol '{}' exceeds maximum length of {} characters",
                 s,
@@ -24,7 +31,11 @@ impl Symbol {
             );
         }
 
-        if !s.chars().all(Self::is_valid_char) {
+        let valid = match dialect {
+            Dialect::Knuth => s.chars().all(Self::is_valid_char),
+            Dialect::Mdk => s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        };
+        if !valid {
             bail!("Invalid characters found in symbol '{}'", s);
         }
 
@@ -53,14 +64,6 @@ impl FromStr for Symbol {
     }
 }
 
-// TODO: Move this to its own module
-// Next there is some notion of a "W-Value" -- this goes in the "address" field of  pseudo-operations,
-// The "W" stands for "Word", so this is meant to represent a full word constant.
-/// Represents a "line" of code in a MIXAL assembly program
-pub struct MixalStatement {
-    loc: Option<Symbol>,
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -92,4 +95,13 @@ mod tests {
         assert!(Symbol::new("LABEL@#").is_err());
         assert!(Symbol::new("LAbEL3").is_err());
     }
+
+    #[test]
+    fn test_mdk_symbol() {
+        assert!(Symbol::parse("LONGER_THAN_TEN", Dialect::Mdk).is_ok());
+        assert!(Symbol::parse("loop2", Dialect::Mdk).is_ok());
+        assert!(Symbol::parse("LONGERTHANTEN", Dialect::Knuth).is_err());
+        assert!(Symbol::parse("with-dash", Dialect::Mdk).is_err());
+        assert!(Symbol::parse("123", Dialect::Mdk).is_err());
+    }
 }
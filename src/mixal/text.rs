@@ -0,0 +1,76 @@
+use anyhow::{Result, bail};
+
+use super::alf::Alf;
+use super::assemble::Dialect;
+
+/// Extension pseudo-operation that assembles a string of any length into consecutive
+/// words, five characters at a time, as a run of ALF statements would
+#[derive(Debug, PartialEq)]
+pub struct Text {
+    pub words: Vec<Alf>,
+}
+
+impl Text {
+    /// Parses a string in double quotes. The last word is padded with blanks.
+    pub fn parse(s: &str, dialect: Dialect) -> Result<Self> {
+        let s = s.trim();
+        let Some(text) = s.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) else {
+            bail!("TEXT must be given a string in double quotes: {}", s);
+        };
+        if text.is_empty() {
+            bail!("TEXT must be given at least one character");
+        }
+
+        let chars: Vec<_> = text.chars().collect();
+        let words = chars
+            .chunks(5)
+            .map(|chunk| {
+                let mut chars = [' '; 5];
+                chars[..chunk.len()].copy_from_slice(chunk);
+                Alf::new(chars, dialect.charset())
+            })
+            .collect::<Result<_>>()?;
+        Ok(Text { words })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = Text::parse("\"HELLO WORLD\"", Dialect::Knuth).unwrap();
+        let chars: Vec<_> = text.words.iter().map(|w| w.chars).collect();
+        assert_eq!(
+            chars,
+            [
+                ['H', 'E', 'L', 'L', 'O'],
+                [' ', 'W', 'O', 'R', 'L'],
+                ['D', ' ', ' ', ' ', ' ']
+            ]
+        );
+        assert_eq!(text.words[2].codes, [4, 0, 0, 0, 0]);
+
+        let text = Text::parse(" \"12345\" ", Dialect::Knuth).unwrap();
+        assert_eq!(text.words.len(), 1);
+        // Quotes inside the string are just more characters
+        assert_eq!(
+            Text::parse("\"A\"B\"", Dialect::Knuth)
+                .unwrap_err()
+                .to_string(),
+            "Invalid character in A\"B  "
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Text::parse("HELLO", Dialect::Knuth).is_err());
+        assert!(Text::parse("\"HELLO", Dialect::Knuth).is_err());
+        assert!(Text::parse("\"\"", Dialect::Knuth).is_err());
+        assert!(Text::parse("\"", Dialect::Knuth).is_err());
+        assert!(Text::parse("\"hello\"", Dialect::Knuth).is_err());
+        assert!(Text::parse("\"A~\"", Dialect::Knuth).is_err());
+        assert!(Text::parse("\"A~\"", Dialect::Mdk).is_ok());
+    }
+}
@@ -1,46 +1,71 @@
 use anyhow::{Result, bail};
 use std::str::FromStr;
 
+use crate::mixal::assemble::{BYTE_SIZE, Dialect};
 use crate::mixal::field::Field;
+use crate::word::Word;
 
-use super::expression::Expression;
+use super::expression::{Environment, Expression};
+use super::symbol::Symbol;
 
 /// A "Word Value" in MIXAL. A sort of inline program, a sequence of expressions
 /// and field lookups that eventually evaluate to a constant. Used with MIXAL
 /// pseudo-operations, but not part of the machine language itself.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum WVal {
     FutureRef(FutureRef),
     WValInner(WValInner),
 }
 
-impl FromStr for WVal {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() > 10 {
-            anyhow::bail!("WValue too long, maximum 10 characters: {}", s)
+impl WVal {
+    /// Evaluates the W-value to a full word. Literal constants can't be evaluated on
+    /// their own, since they stand for an address the assembler has to choose.
+    pub fn evaluate(&self, env: &Environment) -> Result<Word> {
+        match self {
+            WVal::FutureRef(_) => {
+                bail!("Literal constants are only allowed in the address part of an instruction")
+            }
+            WVal::WValInner(inner) => inner.evaluate(env),
         }
+    }
+}
+
+impl WVal {
+    /// Parses a W-value, or a literal constant wrapped in '=' signs
+    pub fn parse(s: &str, dialect: Dialect) -> Result<Self> {
         if s.starts_with("=") && s.ends_with("=") {
-            return Ok(WVal::FutureRef(s.parse()?));
+            return Ok(WVal::FutureRef(FutureRef::parse(s, dialect)?));
         }
 
-        Ok(WVal::WValInner(s.parse()?))
+        Ok(WVal::WValInner(WValInner::parse(s, dialect)?))
+    }
+}
+
+impl FromStr for WVal {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WVal::parse(s, Dialect::Knuth)
     }
 }
 
 /// A W-value that is wrapped in '=' signs stores the result of the value at a
 /// location in memory and resolves to that address, rather than the result of the
 /// value itself
-#[derive(Debug, PartialEq)]
-struct FutureRef {
-    wval: WValInner,
+#[derive(Debug, Clone, PartialEq)]
+pub struct FutureRef {
+    pub wval: WValInner,
 }
 
-impl FromStr for FutureRef {
-    type Err = anyhow::Error;
+impl FutureRef {
+    /// Evaluates the constant that will be stored at the literal's address
+    pub fn evaluate(&self, env: &Environment) -> Result<Word> {
+        self.wval.evaluate(env)
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl FutureRef {
+    pub fn parse(s: &str, dialect: Dialect) -> Result<Self> {
         if !(s.starts_with("=") && s.ends_with("=") && s.len() > 2) {
             anyhow::bail!(
                 "Invalid WValue future reference, missing wrapping '=': {}",
@@ -48,54 +73,101 @@ impl FromStr for FutureRef {
             );
         }
 
+        // Knuth limits literal constants to less than 10 characters between the '=' signs
+        if s.len() - 2 >= 10 {
+            anyhow::bail!("Literal constant too long, maximum 9 characters: {}", s)
+        }
+
         Ok(Self {
-            wval: s[1..s.len() - 1].parse()?,
+            wval: WValInner::parse(&s[1..s.len() - 1], dialect)?,
         })
     }
 }
 
-#[derive(Debug, PartialEq)]
-struct WValInner {
+impl FromStr for FutureRef {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FutureRef::parse(s, Dialect::Knuth)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WValInner {
     components: Vec<WValComponent>,
 }
 
-impl FromStr for WValInner {
-    type Err = anyhow::Error;
+impl WValInner {
+    /// Starting from +0, stores each component's value into its field, from left to right
+    pub fn evaluate(&self, env: &Environment) -> Result<Word> {
+        let mut word = Word::ZERO;
+        for component in &self.components {
+            let value = Word::from_value(component.expression.evaluate(env)?, BYTE_SIZE)?;
+            word.set_field(component.field.bounds(env)?, &value);
+        }
+        Ok(word)
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+    /// Every symbol referenced by the W-value, from left to right
+    pub fn symbols(&self) -> Vec<&Symbol> {
+        self.components
+            .iter()
+            .flat_map(|c| c.expression.symbols())
+            .collect()
+    }
+}
+
+impl WValInner {
+    pub fn parse(s: &str, dialect: Dialect) -> Result<Self> {
         Ok(WValInner {
             // parse each component individually, if any fail the collect::<Result<Vec>>
             // call will result in an error which is bubbled up by '?'.
             components: s
                 .split(",")
-                .map(|s| s.parse())
+                .map(|s| WValComponent::parse(s, dialect))
                 .collect::<Result<Vec<_>, _>>()?,
         })
     }
 }
 
+impl FromStr for WValInner {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WValInner::parse(s, Dialect::Knuth)
+    }
+}
+
 /// Each component consists of an expression (which is evaluated normally) and a field
 /// look up, which selects a portion of the expression result.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 struct WValComponent {
     expression: Expression,
     field: Field,
 }
 
+impl WValComponent {
+    fn parse(s: &str, dialect: Dialect) -> Result<Self> {
+        let (field, idx) = Field::find_field_or_default(s, "0:5", dialect)?;
+        Ok(WValComponent {
+            expression: Expression::parse(&s[0..idx], dialect)?,
+            field,
+        })
+    }
+}
+
 impl FromStr for WValComponent {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (field, idx) = Field::find_field_or_default(s, "0:5")?;
-        Ok(WValComponent {
-            expression: s[0..idx].parse()?,
-            field,
-        })
+        WValComponent::parse(s, Dialect::Knuth)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use super::*;
 
     fn component(expr: &str, field: &str) -> WValComponent {
@@ -239,10 +311,40 @@ mod tests {
         );
     }
 
+    fn eval(s: &str) -> Result<Word> {
+        let symbols = HashMap::from([("X".to_string(), 2)]);
+        let env = Environment {
+            symbols: &symbols,
+            location: 100,
+        };
+        s.parse::<WVal>()?.evaluate(&env)
+    }
+
+    #[test]
+    fn test_wval_evaluate() {
+        assert_eq!(eval("5").unwrap().value(BYTE_SIZE), 5);
+        assert_eq!(eval("-X").unwrap().value(BYTE_SIZE), -2);
+        assert_eq!(
+            eval("1(1:1),X(5:5)").unwrap().to_bytes(),
+            [0, 1, 0, 0, 0, 2]
+        );
+        assert_eq!(
+            eval("1,-1000(0:2)").unwrap().to_bytes(),
+            [1, 15, 40, 0, 0, 1]
+        );
+        assert_eq!(eval("*(4:5)").unwrap().value(BYTE_SIZE), 100);
+
+        assert!(eval("=5=").is_err());
+        assert!(eval("1(5:4)").is_err());
+        assert!(eval("Y").is_err());
+    }
+
     #[test]
     fn test_wval_invalid() {
-        // Too long (over 10 characters)
+        // Number too long (over 10 digits)
         assert!("12345678901".parse::<WVal>().is_err());
+        // Literal constant too long (10 characters or more)
+        assert!("=1000+20000=".parse::<WVal>().is_err());
         // Malformed future ref (missing closing =)
         assert!("=5".parse::<WVal>().is_err());
     }
@@ -0,0 +1,287 @@
+use std::fmt;
+
+use anyhow::{Result, bail};
+
+/// The number of bytes in a MIX word, not counting the sign
+pub const BYTES: usize = 5;
+
+/// The sign of a MIX word. Words keep their sign even when all their bytes are
+/// zero, so there is a minus zero distinct from plus zero.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Sign {
+    #[default]
+    Plus,
+    Minus,
+}
+
+impl Sign {
+    /// The sign of an integer, with zero being positive
+    pub fn of(value: i64) -> Self {
+        if value < 0 { Sign::Minus } else { Sign::Plus }
+    }
+
+    pub fn flip(self) -> Self {
+        match self {
+            Sign::Plus => Sign::Minus,
+            Sign::Minus => Sign::Plus,
+        }
+    }
+}
+
+/// A field specification (L:R) of a word, where byte 0 is the sign. In an
+/// instruction it is encoded in the F-part as 8L+R.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSpec {
+    pub left: usize,
+    pub right: usize,
+}
+
+impl FieldSpec {
+    /// The whole word, (0:5)
+    pub const WHOLE: FieldSpec = FieldSpec { left: 0, right: 5 };
+
+    pub fn new(left: usize, right: usize) -> Result<Self> {
+        if left > right || right > BYTES {
+            bail!("Invalid field specifier ({}:{})", left, right);
+        }
+        Ok(FieldSpec { left, right })
+    }
+
+    /// Decodes an F-part of 8L+R
+    pub fn from_byte(f: u8) -> Result<Self> {
+        Self::new(f as usize / 8, f as usize % 8)
+    }
+
+    pub fn to_byte(self) -> u8 {
+        (8 * self.left + self.right) as u8
+    }
+
+    /// The bytes of the field, not counting the sign
+    fn bytes(self) -> std::ops::RangeInclusive<usize> {
+        self.left.max(1)..=self.right
+    }
+}
+
+impl fmt::Display for FieldSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}:{})", self.left, self.right)
+    }
+}
+
+/// A MIX word: a sign and five bytes. How many values a byte holds depends on the
+/// machine, anywhere from 64 to 100, so converting to and from integers takes the
+/// byte size, while moving bytes between fields doesn't need it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Word {
+    pub sign: Sign,
+    pub bytes: [u8; BYTES],
+}
+
+impl Word {
+    pub const ZERO: Word = Word {
+        sign: Sign::Plus,
+        bytes: [0; BYTES],
+    };
+
+    pub fn new(sign: Sign, bytes: [u8; BYTES]) -> Self {
+        Word { sign, bytes }
+    }
+
+    /// Builds a word holding the given value, which must fit in five bytes
+    pub fn from_value(value: i64, byte_size: i64) -> Result<Self> {
+        Self::from_magnitude(Sign::of(value), value.unsigned_abs(), BYTES, byte_size)
+    }
+
+    /// Builds a word for an index register: a sign and two bytes, in bytes 4 and 5
+    pub fn from_index(value: i64, byte_size: i64) -> Result<Self> {
+        Self::from_magnitude(Sign::of(value), value.unsigned_abs(), 2, byte_size)
+    }
+
+    /// Builds a word with the given sign and magnitude, which must fit in the
+    /// rightmost `width` bytes. A zero magnitude keeps its sign.
+    pub fn from_magnitude(
+        sign: Sign,
+        magnitude: u64,
+        width: usize,
+        byte_size: i64,
+    ) -> Result<Self> {
+        let mut bytes = [0; BYTES];
+        let mut rest = magnitude;
+        for byte in bytes[BYTES - width..].iter_mut().rev() {
+            *byte = (rest % byte_size as u64) as u8;
+            rest /= byte_size as u64;
+        }
+        if rest != 0 {
+            bail!("Value {} does not fit in {} bytes", magnitude, width);
+        }
+        Ok(Word { sign, bytes })
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.sign == Sign::Minus
+    }
+
+    pub fn is_minus_zero(&self) -> bool {
+        self.is_negative() && self.bytes == [0; BYTES]
+    }
+
+    /// The magnitude of the word, ignoring the sign
+    pub fn magnitude(&self, byte_size: i64) -> i64 {
+        self.bytes
+            .iter()
+            .fold(0, |acc, &b| acc * byte_size + b as i64)
+    }
+
+    /// The signed value of the word. Minus zero is just zero.
+    pub fn value(&self, byte_size: i64) -> i64 {
+        match self.sign {
+            Sign::Plus => self.magnitude(byte_size),
+            Sign::Minus => -self.magnitude(byte_size),
+        }
+    }
+
+    /// Whether the word can be held by an index register, i.e. bytes 1-3 are zero
+    pub fn is_index(&self) -> bool {
+        self.bytes[..3] == [0; 3]
+    }
+
+    /// The contents of the field (L:R), shifted right the way LDA loads them. The
+    /// sign is taken from the word if L = 0 and is + otherwise.
+    pub fn field(&self, field: FieldSpec) -> Word {
+        let sign = if field.left == 0 {
+            self.sign
+        } else {
+            Sign::Plus
+        };
+        let mut bytes = [0; BYTES];
+        let source = &self.bytes[field.left.max(1) - 1..field.right];
+        bytes[BYTES - source.len()..].copy_from_slice(source);
+        Word { sign, bytes }
+    }
+
+    /// Stores `source` into the field (L:R) of this word the way STA would: the
+    /// rightmost bytes of `source` fill the field, and the sign is copied if L = 0
+    pub fn set_field(&mut self, field: FieldSpec, source: &Word) {
+        if field.left == 0 {
+            self.sign = source.sign;
+        }
+        let bytes = field.bytes();
+        let width = bytes.clone().count();
+        self.bytes[bytes.start() - 1..*bytes.end()].copy_from_slice(&source.bytes[BYTES - width..]);
+    }
+
+    /// The word as a byte for the sign (0 for plus, 1 for minus) and its five bytes,
+    /// as it is stored in a memory image
+    pub fn to_bytes(&self) -> [u8; BYTES + 1] {
+        let mut bytes = [0; BYTES + 1];
+        bytes[0] = self.is_negative() as u8;
+        bytes[1..].copy_from_slice(&self.bytes);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: [u8; BYTES + 1]) -> Self {
+        let sign = if bytes[0] == 0 {
+            Sign::Plus
+        } else {
+            Sign::Minus
+        };
+        Word {
+            sign,
+            bytes: bytes[1..].try_into().unwrap(),
+        }
+    }
+}
+
+impl fmt::Display for Word {
+    /// Shows the sign and each byte, e.g. "+ 01 00 00 02 08"
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", if self.is_negative() { '-' } else { '+' })?;
+        for byte in &self.bytes {
+            write!(f, " {:02}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values() {
+        assert_eq!(Word::from_value(0, 64).unwrap(), Word::ZERO);
+        assert_eq!(
+            Word::from_value(65, 64).unwrap(),
+            Word::new(Sign::Plus, [0, 0, 0, 1, 1])
+        );
+        assert_eq!(
+            Word::from_value(-1, 64).unwrap(),
+            Word::new(Sign::Minus, [0, 0, 0, 0, 1])
+        );
+        assert_eq!(
+            Word::from_value(1_234_567_890, 100).unwrap(),
+            Word::new(Sign::Plus, [12, 34, 56, 78, 90])
+        );
+        assert_eq!(Word::from_value(-4161, 64).unwrap().value(64), -4161);
+        assert!(Word::from_value(64i64.pow(5), 64).is_err());
+        assert!(Word::from_value(-(64i64.pow(5) - 1), 64).is_ok());
+        assert!(Word::from_value(100i64.pow(5), 100).is_err());
+    }
+
+    #[test]
+    fn test_minus_zero() {
+        let minus_zero = Word::from_magnitude(Sign::Minus, 0, BYTES, 64).unwrap();
+        assert!(minus_zero.is_minus_zero());
+        assert_ne!(minus_zero, Word::ZERO);
+        assert_eq!(minus_zero.value(64), 0);
+        assert!(!Word::ZERO.is_minus_zero());
+        assert_eq!(minus_zero.to_string(), "- 00 00 00 00 00");
+        assert_eq!(Word::from_bytes(minus_zero.to_bytes()), minus_zero);
+    }
+
+    #[test]
+    fn test_index() {
+        assert_eq!(
+            Word::from_index(-4095, 64).unwrap(),
+            Word::new(Sign::Minus, [0, 0, 0, 63, 63])
+        );
+        assert!(Word::from_index(4096, 64).is_err());
+        assert!(Word::from_index(9999, 100).is_ok());
+        assert!(Word::from_index(4095, 64).unwrap().is_index());
+        assert!(!Word::from_value(4096, 64).unwrap().is_index());
+    }
+
+    #[test]
+    fn test_field_spec() {
+        assert_eq!(
+            FieldSpec::from_byte(13).unwrap(),
+            FieldSpec::new(1, 5).unwrap()
+        );
+        assert_eq!(FieldSpec::WHOLE.to_byte(), 5);
+        assert_eq!(FieldSpec::new(4, 4).unwrap().to_string(), "(4:4)");
+        assert!(FieldSpec::from_byte(6).is_err());
+        assert!(FieldSpec::from_byte(8 * 3 + 2).is_err());
+    }
+
+    #[test]
+    fn test_field_sign() {
+        // The sign comes along only when L = 0, whether loading or storing
+        let word = Word::new(Sign::Minus, [1, 16, 3, 5, 4]);
+        let field = |l, r| FieldSpec::new(l, r).unwrap();
+        assert_eq!(
+            word.field(field(0, 3)),
+            Word::new(Sign::Minus, [0, 0, 1, 16, 3])
+        );
+        assert_eq!(
+            word.field(field(4, 5)),
+            Word::new(Sign::Plus, [0, 0, 0, 5, 4])
+        );
+
+        let register = Word::new(Sign::Plus, [6, 7, 8, 9, 0]);
+        let mut location = word;
+        location.set_field(field(2, 3), &register);
+        assert_eq!(location, Word::new(Sign::Minus, [1, 9, 0, 5, 4]));
+        location.set_field(field(0, 1), &register);
+        assert_eq!(location, Word::new(Sign::Plus, [0, 9, 0, 5, 4]));
+    }
+}
@@ -0,0 +1,183 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+/// Runs the mix binary with the given arguments, feeding `stdin` to it
+fn mix(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mix"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The binary may exit on a usage error before reading its input
+    let _ = child.stdin.take().unwrap().write_all(stdin.as_bytes());
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn test_asm_then_dis() {
+    let asm = mix(&["asm", "test_data/findmax.mixal"], "");
+    assert!(asm.status.success());
+
+    let image = String::from_utf8(asm.stdout).unwrap();
+    assert!(image.contains("\n3000 + 47 01 00 02 32\n"));
+
+    let dis = mix(&["dis"], &image);
+    assert!(dis.status.success());
+    let text = String::from_utf8(dis.stdout).unwrap();
+    assert!(text.contains("STJ  3009"));
+}
+
+#[test]
+fn test_predefined_symbols() {
+    let source = " ORIG BUF\n CON SIZE\n END BUF\n";
+    let asm = mix(&["asm", "-D", "BUF=100", "-D", "SIZE=BUF*2"], source);
+    assert!(asm.status.success());
+    assert_eq!(
+        String::from_utf8(asm.stdout).unwrap(),
+        "start 100\n0100 + 00 00 00 03 08\n"
+    );
+
+    assert_eq!(mix(&["asm", "-D", "BUF"], source).status.code(), Some(2));
+    // Names are checked against the symbol rules of the dialect
+    assert_eq!(mix(&["asm", "-D", "buf=1"], source).status.code(), Some(4));
+    let mdk = mix(
+        &["asm", "--dialect", "mdk", "-D", "LONGBUFFERSIZE=100"],
+        " con LONGBUFFERSIZE\n end 0\n",
+    );
+    assert!(mdk.status.success());
+}
+
+#[test]
+fn test_rendered_diagnostics() {
+    let asm = mix(&["asm", "--color=never"], "X EQU 1\nX EQU 2\n END 0\n");
+    assert_eq!(
+        String::from_utf8(asm.stderr).unwrap(),
+        "\
+error[E0002]: Symbol 'X' is defined more than once
+ --> <stdin>:2:1
+  |
+2 | X EQU 2
+  | ^
+note: symbol first defined here
+ --> <stdin>:1:1
+  |
+1 | X EQU 1
+  | ^
+
+error: aborting due to 1 previous error
+"
+    );
+}
+
+#[test]
+fn test_machine_readable_diagnostics() {
+    let source = "X EQU 1\nX EQU 2\n END 0\n";
+    let json = mix(&["asm", "--diagnostics=json"], source);
+    assert_eq!(json.status.code(), Some(4));
+    assert_eq!(
+        String::from_utf8(json.stderr).unwrap(),
+        "[{\"code\":\"E0002\",\"end_column\":2,\"file\":\"<stdin>\",\"line\":2,\
+         \"message\":\"Symbol 'X' is defined more than once\",\"name\":\"duplicate-symbol\",\
+         \"notes\":[{\"end_column\":2,\"file\":\"<stdin>\",\"line\":1,\"message\":\"symbol first defined here\",\
+         \"start_column\":1}],\"severity\":\"error\",\"start_column\":1}]\n"
+    );
+
+    let sarif = mix(&["asm", "--diagnostics=sarif"], source);
+    let log = String::from_utf8(sarif.stderr).unwrap();
+    assert!(log.starts_with('{') && log.trim_end().ends_with('}'));
+    assert!(log.contains("\"ruleId\":\"E0002\""));
+}
+
+#[test]
+fn test_exit_codes() {
+    assert_eq!(mix(&["asm"], " BOGUS 1\n").status.code(), Some(3));
+    assert_eq!(mix(&["asm"], " LDA 1,Z\n").status.code(), Some(4));
+    assert_eq!(
+        mix(&["asm", "test_data/missing.mixal"], "").status.code(),
+        Some(1)
+    );
+    assert_eq!(mix(&["asm", "--bogus"], "").status.code(), Some(2));
+    // Undefined symbols are only a warning unless in strict mode
+    assert_eq!(mix(&["asm"], " JMP Z\n END 0\n").status.code(), Some(0));
+    assert_eq!(
+        mix(&["asm", "--strict"], " JMP Z\n END 0\n").status.code(),
+        Some(4)
+    );
+}
+
+#[test]
+fn test_run() {
+    let asm = mix(&["asm"], " ORIG 100\nSTART NOP\n HLT\n END START\n");
+    assert!(asm.status.success());
+    let run = mix(&["run"], &String::from_utf8(asm.stdout).unwrap());
+    assert!(run.status.success());
+    let registers = String::from_utf8(run.stdout).unwrap();
+    assert!(registers.starts_with("rA  + 00 00 00 00 00\n"));
+
+    let asm = mix(&["asm"], "START NOP 0,7\n END START\n");
+    let run = mix(&["run"], &String::from_utf8(asm.stdout).unwrap());
+    assert_eq!(run.status.code(), Some(5));
+    assert_eq!(
+        String::from_utf8(run.stderr).unwrap(),
+        "error: Fault at 0000: Invalid index register 7\n"
+    );
+}
+
+#[test]
+fn test_run_binary_shift() {
+    let source = "X EQU 2000\n ORIG X\n CON 7\nSTART LDA X\n SLB 1\n HLT\n END START\n";
+    let image = String::from_utf8(mix(&["asm"], source).stdout).unwrap();
+    let run = mix(&["run"], &image);
+    assert!(run.status.success());
+    let registers = String::from_utf8(run.stdout).unwrap();
+    assert!(registers.starts_with("rA  + 00 00 00 00 14\n"));
+}
+
+#[test]
+fn test_debug() {
+    let path = std::env::temp_dir().join(format!("debug-{}.img", std::process::id()));
+    let path = path.to_str().unwrap();
+    let source = " ORIG 100\nSTART LDA 200\n HLT\n ORIG 200\n CON 7\n END START\n";
+    assert!(mix(&["asm", "-o", path], source).status.success());
+
+    // The final state can be looked at after HLT
+    let debug = mix(&["debug", path], "s\ns\nm 200\nr\ns\nq\n");
+    std::fs::remove_file(path).unwrap();
+    assert!(debug.status.success());
+    let output = String::from_utf8(debug.stdout).unwrap();
+    assert!(output.starts_with(
+        "LDA  200  ; 0100\n\
+         (mix) HLT    ; 0101\n\
+         (mix) Halted after 2 instructions\n\
+         (mix) 0200 + 00 00 00 00 07\n\
+         (mix) rA  + 00 00 00 00 07\n"
+    ));
+    assert!(output.ends_with("(mix) (mix) "));
+    assert_eq!(
+        String::from_utf8(debug.stderr).unwrap(),
+        "The program has halted\n"
+    );
+}
+
+#[test]
+fn test_mixdis_binary_image() {
+    let path = std::env::temp_dir().join(format!("mixdis-{}.img", std::process::id()));
+    let path = path.to_str().unwrap();
+    let asm = mix(
+        &["asm", "--format", "binary", "-o", path],
+        " ORIG 3000\nSTART LDA 2000\n HLT\n END START\n",
+    );
+    assert!(asm.status.success());
+
+    let dis = Command::new(env!("CARGO_BIN_EXE_mixdis"))
+        .arg(path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert!(dis.status.success());
+    let text = String::from_utf8(dis.stdout).unwrap();
+    assert!(text.contains("LDA  2000\n"));
+    assert!(text.ends_with("END  3000\n"));
+}
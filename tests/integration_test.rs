@@ -1,6 +1,159 @@
-use mix_system::mixal::assemble::assemble_file;
+use mix_system::emulator::machine::Machine;
+use mix_system::mixal::assemble::{assemble_file, assemble_source};
 
 #[test]
 fn test_mixal() -> anyhow::Result<()> {
-    assemble_file("test_data/findmax.mixal")
+    assemble_file("test_data/findmax.mixal")?;
+    Ok(())
+}
+
+#[test]
+fn test_findmax_debug_info() -> anyhow::Result<()> {
+    let assembly = assemble_file("test_data/findmax.mixal")?;
+    let info = &assembly.debug_info;
+    assert_eq!(info.describe(3000), "MAXIMUM (test_data/findmax.mixal:3)");
+    assert_eq!(info.describe(3006), "CHANGEM+1 (test_data/findmax.mixal:9)");
+    assert_eq!(info.describe(3011), "START+1 (test_data/findmax.mixal:15)");
+    assert_eq!(info.symbol("X").map(|s| s.value), Some(1000));
+    assert_eq!(
+        info.to_string()
+            .parse::<mix_system::mixal::debug_info::DebugInfo>()?,
+        *info
+    );
+    Ok(())
+}
+
+#[test]
+fn test_run_findmax() -> anyhow::Result<()> {
+    let assembly = assemble_file("test_data/findmax.mixal")?;
+    let mut machine = Machine::default();
+    machine.load(&assembly.image)?;
+    machine.run()?;
+    // The maximum is 12, and X[4] is the last place it occurs
+    assert_eq!(machine.a.value(64), 12);
+    assert_eq!(machine.i[1].value(64), 4);
+    assert_eq!(machine.pc, 3013);
+    Ok(())
+}
+
+#[test]
+fn test_binary_instructions_on_decimal_machine() -> anyhow::Result<()> {
+    let assembly = assemble_source(" ORIG 100\nSTART ENTA 4\n JAE *+1\n HLT\n END START\n", "-")?;
+    let mut machine = Machine::new(100)?;
+    machine.load(&assembly.image)?;
+    let fault = machine.run().unwrap_err();
+    assert_eq!(fault.location, 101);
+    assert_eq!(
+        fault.message,
+        "JAE needs a binary MIX with 64-valued bytes, not 100-valued ones"
+    );
+    Ok(())
+}
+
+/// Runs the subroutines of Programs A and M and the floating point instructions
+/// on the same operands, and checks that they agree bit for bit, including on
+/// whether the exponent overflowed or underflowed
+#[cfg(feature = "float")]
+#[test]
+fn test_knuth_float_programs() -> anyhow::Result<()> {
+    use mix_system::word::{Sign, Word};
+
+    let assembly = assemble_file("test_data/float.mixal")?;
+    let symbol = |name: &str| assembly.symbols[name] as usize;
+    let run = |entry: &str, u: Word, v: Word| -> anyhow::Result<(Word, Word)> {
+        let mut machine = Machine::default();
+        machine.load(&assembly.image)?;
+        machine.memory[symbol("ACC")] = u;
+        machine.memory[symbol("V")] = v;
+        machine.pc = symbol(entry);
+        machine.run()?;
+        Ok((
+            machine.memory[symbol("ACC")],
+            machine.memory[symbol("FLAG")],
+        ))
+    };
+
+    let float = |sign, bytes| Word::new(sign, bytes);
+    let operands = [
+        // 1.5 and 0.75 with q = 32, which add and multiply exactly
+        (
+            float(Sign::Plus, [33, 1, 32, 0, 0]),
+            float(Sign::Plus, [32, 48, 0, 0, 0]),
+        ),
+        // Opposite signs, with v's digits shifted into rX
+        (
+            float(Sign::Plus, [40, 1, 2, 3, 4]),
+            float(Sign::Minus, [37, 5, 6, 7, 8]),
+        ),
+        (
+            float(Sign::Minus, [40, 1, 0, 0, 0]),
+            float(Sign::Plus, [35, 63, 63, 63, 63]),
+        ),
+        // Cancellation to zero
+        (
+            float(Sign::Minus, [30, 9, 9, 9, 9]),
+            float(Sign::Minus, [30, 9, 9, 9, 9]),
+        ),
+        // Sums that round up, down and to odd, and carry into a fifth digit
+        (
+            float(Sign::Plus, [32, 1, 0, 0, 1]),
+            float(Sign::Plus, [28, 33, 0, 0, 0]),
+        ),
+        (
+            float(Sign::Plus, [32, 1, 0, 0, 2]),
+            float(Sign::Plus, [28, 32, 0, 0, 0]),
+        ),
+        (
+            float(Sign::Plus, [32, 1, 0, 0, 3]),
+            float(Sign::Plus, [28, 32, 0, 0, 0]),
+        ),
+        (
+            float(Sign::Minus, [32, 1, 0, 0, 3]),
+            float(Sign::Plus, [27, 32, 0, 0, 1]),
+        ),
+        (
+            float(Sign::Plus, [32, 63, 63, 63, 63]),
+            float(Sign::Plus, [30, 1, 0, 0, 0]),
+        ),
+        // Products and quotients that round
+        (
+            float(Sign::Minus, [32, 63, 62, 61, 60]),
+            float(Sign::Plus, [33, 59, 58, 57, 56]),
+        ),
+        (
+            float(Sign::Plus, [32, 1, 0, 0, 0]),
+            float(Sign::Minus, [32, 3, 0, 0, 0]),
+        ),
+        // Exponent overflow and underflow
+        (
+            float(Sign::Plus, [63, 63, 63, 63, 63]),
+            float(Sign::Plus, [63, 1, 0, 0, 0]),
+        ),
+        (
+            float(Sign::Plus, [60, 40, 0, 0, 0]),
+            float(Sign::Minus, [5, 20, 0, 0, 0]),
+        ),
+        (
+            float(Sign::Minus, [1, 1, 0, 0, 0]),
+            float(Sign::Plus, [2, 1, 0, 0, 0]),
+        ),
+        (
+            float(Sign::Plus, [0, 1, 2, 3, 4]),
+            float(Sign::Plus, [0, 1, 0, 0, 1]),
+        ),
+        (
+            float(Sign::Plus, [2, 1, 0, 0, 0]),
+            float(Sign::Plus, [60, 2, 0, 0, 0]),
+        ),
+        // Division by zero
+        (float(Sign::Plus, [32, 1, 0, 0, 0]), Word::ZERO),
+    ];
+    for (u, v) in operands {
+        for op in ["ADD", "SUB", "MUL", "DIV"] {
+            let knuth = run(&format!("K{}", op), u, v)?;
+            let hardware = run(&format!("H{}", op), u, v)?;
+            assert_eq!(knuth, hardware, "F{} of {} and {}", op, u, v);
+        }
+    }
+    Ok(())
 }